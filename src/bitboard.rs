@@ -0,0 +1,128 @@
+//! Backtracking solver over the flat layout of `Sudoku::to_flat` that only needs `core`,
+//! so it is available even without the `alloc` feature.
+
+const N: usize = 9;
+
+/// Candidate bitmasks (bit `1 << digit`) already used by each row, column and box
+struct Masks {
+    rows: [u16; N],
+    cols: [u16; N],
+    boxes: [u16; N],
+}
+
+impl Masks {
+    fn new(cells: &[u8; N * N]) -> Option<Self> {
+        let mut masks = Self {
+            rows: [0; N],
+            cols: [0; N],
+            boxes: [0; N],
+        };
+        for (k, &x) in cells.iter().enumerate() {
+            match x {
+                0 => {}
+                1..=9 => {
+                    let bit = 1 << x;
+                    if masks.used(k) & bit != 0 {
+                        return None;
+                    }
+                    masks.toggle(k, bit);
+                }
+                _ => return None,
+            }
+        }
+        Some(masks)
+    }
+
+    fn used(&self, k: usize) -> u16 {
+        let (i, j) = (k / N, k % N);
+        self.rows[i] | self.cols[j] | self.boxes[(i / 3) * 3 + j / 3]
+    }
+
+    fn toggle(&mut self, k: usize, bit: u16) {
+        let (i, j) = (k / N, k % N);
+        self.rows[i] ^= bit;
+        self.cols[j] ^= bit;
+        self.boxes[(i / 3) * 3 + j / 3] ^= bit;
+    }
+}
+
+const ALL_DIGITS: u16 = 0b11_1111_1110;
+
+/// Solves `cells` in place (row-major, 0 for empty cells). Returns false, leaving `cells`
+/// as it was, when the board is invalid or has no solution.
+pub fn solve_flat(cells: &mut [u8; N * N]) -> bool {
+    match Masks::new(cells) {
+        Some(mut masks) => backtrack(cells, &mut masks),
+        None => false,
+    }
+}
+
+/// Branches on the empty cell with the fewest candidates
+fn backtrack(cells: &mut [u8; N * N], masks: &mut Masks) -> bool {
+    let mut best: Option<(usize, u16)> = None;
+    for k in (0..N * N).filter(|&k| cells[k] == 0) {
+        let candidates = ALL_DIGITS & !masks.used(k);
+        if best.is_none_or(|(_, c)| candidates.count_ones() < c.count_ones()) {
+            best = Some((k, candidates));
+            if candidates.count_ones() <= 1 {
+                break;
+            }
+        }
+    }
+    let (k, candidates) = match best {
+        Some(best) => best,
+        None => return true,
+    };
+    for x in 1..=N as u8 {
+        let bit = 1 << x;
+        if candidates & bit == 0 {
+            continue;
+        }
+        cells[k] = x;
+        masks.toggle(k, bit);
+        if backtrack(cells, masks) {
+            return true;
+        }
+        masks.toggle(k, bit);
+    }
+    cells[k] = 0;
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> [u8; N * N] {
+        let mut cells = [0; N * N];
+        for (cell, c) in cells.iter_mut().zip(s.chars()) {
+            *cell = c.to_digit(10).unwrap() as u8;
+        }
+        cells
+    }
+
+    #[test]
+    fn solve_flat_works() {
+        let mut cells = parse(
+            "100007090030020008009600500005300900010080002600004000300000010040000007007000300",
+        );
+        assert!(solve_flat(&mut cells));
+        let expected = parse(
+            "162857493534129678789643521475312986913586742628794135356478219241935867897261354",
+        );
+        assert_eq!(cells, expected);
+    }
+
+    #[test]
+    fn solve_flat_fails_without_solution() {
+        let original = parse(
+            "023456789000000000000000000000000000100000000000000000000000000000000000000000000",
+        );
+        let mut cells = original;
+        assert!(!solve_flat(&mut cells));
+        assert_eq!(cells, original);
+        let mut invalid = original;
+        invalid[0] = 2;
+        assert!(!solve_flat(&mut invalid));
+    }
+}
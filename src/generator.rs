@@ -0,0 +1,88 @@
+use crate::{
+    prelude::*,
+    rng::{Rng, SplitMix64},
+    solver::{count_solutions, rate_difficulty, Difficulty},
+    sudoku::Sudoku,
+};
+
+/// Clears the clues of a solved grid in a random order (given by `seed`), keeping every
+/// removal that preserves a unique solution. The result is a minimal puzzle.
+pub fn minimize(full: Sudoku, seed: u64) -> Sudoku {
+    let mut rng = SplitMix64::new(seed);
+    let mut flat = full.to_flat();
+    let mut order = (0..flat.len()).collect::<Vec<_>>();
+    rng.shuffle(&mut order);
+    for k in order {
+        if flat[k] == 0 {
+            continue;
+        }
+        let clue = flat[k];
+        flat[k] = 0;
+        let board = Sudoku::from_flat(&flat).expect("removing clues keeps the board valid");
+        if count_solutions(board, 2) != 1 {
+            flat[k] = clue;
+        }
+    }
+    Sudoku::from_flat(&flat).expect("removing clues keeps the board valid")
+}
+
+/// Clears clues of a solved grid in a random order (given by `seed`), keeping the solution
+/// unique and never rating above `target`. Stops as soon as the puzzle rates as `target`,
+/// returns `None` if the grid can't be carved to that difficulty.
+pub fn carve_to_difficulty(full: Sudoku, target: Difficulty, seed: u64) -> Option<Sudoku> {
+    let mut rng = SplitMix64::new(seed);
+    let mut flat = full.to_flat();
+    let mut order = (0..flat.len()).collect::<Vec<_>>();
+    rng.shuffle(&mut order);
+    for k in order {
+        if flat[k] == 0 {
+            continue;
+        }
+        let clue = flat[k];
+        flat[k] = 0;
+        let board = Sudoku::from_flat(&flat).expect("removing clues keeps the board valid");
+        if count_solutions(board.clone(), 2) != 1 {
+            flat[k] = clue;
+            continue;
+        }
+        match rate_difficulty(&board).cmp(&target) {
+            core::cmp::Ordering::Less => {}
+            core::cmp::Ordering::Equal => return Some(board),
+            core::cmp::Ordering::Greater => flat[k] = clue,
+        }
+    }
+    None
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minimize_works() {
+        let text = " 1
+69  2  57
+    692
+  9   4
+47     2
+581 9   3
+  5  86
+ 4 2  8 1
+   6   4";
+        let full = Sudoku::from_text(text).unwrap().solved().unwrap();
+        let puzzle = minimize(full, 42);
+        println!("{puzzle}");
+        assert_eq!(count_solutions(puzzle.clone(), 2), 1);
+        assert!(puzzle.is_minimal());
+    }
+
+    #[test]
+    fn carve_to_difficulty_works() {
+        let full = Sudoku::from_text("").unwrap().solved().unwrap();
+        let puzzle = carve_to_difficulty(full.clone(), Difficulty::Medium, 7).unwrap();
+        println!("{puzzle}");
+        assert_eq!(rate_difficulty(&puzzle), Difficulty::Medium);
+        assert_eq!(count_solutions(puzzle.clone(), 2), 1);
+        assert_eq!(puzzle.solved().unwrap(), full);
+    }
+}
@@ -1,7 +1,12 @@
+use crate::prelude::*;
+use core::hash::Hash;
+#[cfg(not(feature = "std"))]
+use core::hash::Hasher;
+#[cfg(feature = "std")]
 use log::debug;
+#[cfg(feature = "std")]
 use std::{
     collections::HashSet,
-    hash::Hash,
     sync::{
         atomic::{AtomicBool, AtomicUsize, Ordering},
         mpsc, Arc, Mutex,
@@ -12,6 +17,20 @@ pub trait Graph {
     type Node: Clone + Eq + Hash;
     fn neighbours(&self, node: &Self::Node) -> Vec<Self::Node>;
     fn check_goal(&self, node: &mut Self::Node) -> GraphControl;
+
+    /// Same as `neighbours`, building the new nodes over the ones in `pool` (nodes the search
+    /// is done with) to reuse their storage. By default the pool is left alone.
+    fn neighbours_pooled(&self, node: &Self::Node, pool: &mut Vec<Self::Node>) -> Vec<Self::Node> {
+        let _ = pool;
+        self.neighbours(node)
+    }
+
+    /// Whether each node can only be reached from one parent. The sequential searches then
+    /// don't remember the visited nodes, they go to the pool of `neighbours_pooled` instead
+    /// (which should take them, or the pool grows as the visited set would).
+    fn is_tree(&self) -> bool {
+        false
+    }
 }
 
 pub enum GraphControl {
@@ -20,36 +39,396 @@ pub enum GraphControl {
     Prune,
 }
 
-/// Returns solution and number of iterations
-pub fn dfs<G: Graph>(graph: G, start: G::Node) -> Result<(G::Node, usize), (String, usize)> {
-    let mut iterations = 0;
-    let mut queue = Vec::new();
-    let mut visited = HashSet::new();
-    queue.push(start);
-
-    while let Some(mut node) = queue.pop() {
-        iterations += 1;
-        match graph.check_goal(&mut node) {
-            GraphControl::Finish => {
-                return Ok((node, iterations));
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchError {
+    /// Every reachable node was visited without finding a solution
+    Exhausted,
+    /// The iteration limit was hit before finding a solution
+    LimitReached,
+}
+
+/// Found node and number of iterations, or why the search failed and the iterations it took
+pub type SearchResult<N> = Result<(N, usize), (SearchError, usize)>;
+
+/// Search storage that keeps its allocations between runs of [`dfs_with_scratch`], including
+/// the node pool of [`Graph::neighbours_pooled`]
+pub struct DfsScratch<N> {
+    traversal: Traversal<N>,
+}
+
+impl<N> Default for DfsScratch<N> {
+    fn default() -> Self {
+        Self {
+            traversal: Traversal::default(),
+        }
+    }
+}
+
+/// Observes a [`Traversal`], the searches only differ in these hooks. They do nothing by
+/// default.
+trait Hooks<N> {
+    /// Called before checking each node, `depth` being its distance to the root. Returning
+    /// false stops the traversal.
+    fn visit(&mut self, _node: &N, _depth: usize) -> bool {
+        true
+    }
+    /// Called on each pruned node
+    fn prune(&mut self, _node: &N) {}
+    /// Called on each node that continues, with the number of neighbours queued for it
+    fn expand(&mut self, _node: &N, _queued: usize) {}
+}
+
+impl<N> Hooks<N> for () {}
+
+/// Stops the traversal once the given number of nodes were visited
+struct Limit(usize);
+
+impl<N> Hooks<N> for Limit {
+    fn visit(&mut self, _node: &N, _depth: usize) -> bool {
+        self.0.checked_sub(1).map(|left| self.0 = left).is_some()
+    }
+}
+
+/// How [`Traversal::next_goal`] ended
+enum Outcome<N> {
+    Goal(N),
+    Exhausted,
+    Stopped,
+}
+
+/// Nodes a traversal is done with
+#[cfg(feature = "std")]
+type VisitedSet<N> = HashSet<N>;
+
+/// Nodes a traversal is done with. There is no `HashSet` without std, so the nodes are
+/// kept in buckets picked by their FNV-1a hash.
+#[cfg(not(feature = "std"))]
+struct VisitedSet<N> {
+    buckets: Vec<Vec<N>>,
+    len: usize,
+}
+
+#[cfg(not(feature = "std"))]
+impl<N> VisitedSet<N> {
+    fn new() -> Self {
+        Self {
+            buckets: Vec::new(),
+            len: 0,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.buckets.iter_mut().for_each(Vec::clear);
+        self.len = 0;
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl<N: Eq + Hash> VisitedSet<N> {
+    fn bucket(&self, node: &N) -> usize {
+        let mut hasher = Fnv(0xcbf2_9ce4_8422_2325);
+        node.hash(&mut hasher);
+        (hasher.finish() % self.buckets.len() as u64) as usize
+    }
+
+    fn contains(&self, node: &N) -> bool {
+        !self.buckets.is_empty() && self.buckets[self.bucket(node)].contains(node)
+    }
+
+    fn insert(&mut self, node: N) -> bool {
+        if self.contains(&node) {
+            return false;
+        }
+        // Keeps about two nodes per bucket
+        if self.len >= 2 * self.buckets.len() {
+            let nodes = self.buckets.drain(..).flatten().collect::<Vec<_>>();
+            self.buckets = (0..(2 * self.len).max(16)).map(|_| Vec::new()).collect();
+            for old in nodes {
+                let k = self.bucket(&old);
+                self.buckets[k].push(old);
             }
-            GraphControl::Prune => {}
-            GraphControl::Continue => {
-                for neighbour in graph.neighbours(&node) {
-                    if visited.contains(&neighbour) {
-                        continue;
+        }
+        let k = self.bucket(&node);
+        self.buckets[k].push(node);
+        self.len += 1;
+        true
+    }
+}
+
+/// 64-bit FNV-1a, the hasher of [`VisitedSet`] without std
+#[cfg(not(feature = "std"))]
+struct Fnv(u64);
+
+#[cfg(not(feature = "std"))]
+impl Hasher for Fnv {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+/// Depth-first traversal the searches are built on: the queue of nodes (with their depth),
+/// the visited set, the pool of nodes it is done with and the iteration count. It can be
+/// resumed after each goal.
+struct Traversal<N> {
+    queue: Vec<(N, usize)>,
+    visited: VisitedSet<N>,
+    pool: Vec<N>,
+    iterations: usize,
+}
+
+impl<N> Default for Traversal<N> {
+    fn default() -> Self {
+        Self {
+            queue: Vec::new(),
+            visited: VisitedSet::new(),
+            pool: Vec::new(),
+            iterations: 0,
+        }
+    }
+}
+
+impl<N: Clone + Eq + Hash> Traversal<N> {
+    /// Starts from `roots`, the last one being visited first
+    fn new(roots: impl IntoIterator<Item = N>) -> Self {
+        let mut traversal = Self::default();
+        traversal
+            .queue
+            .extend(roots.into_iter().map(|root| (root, 0)));
+        traversal
+    }
+
+    /// Starts again from `start`, keeping the allocations and the pool
+    fn restart(&mut self, start: N) {
+        self.queue.clear();
+        self.visited.clear();
+        self.iterations = 0;
+        self.queue.push((start, 0));
+    }
+
+    /// Visits nodes until one of them is a goal, the queue runs out or `hooks` stops it
+    fn next_goal<G>(&mut self, graph: &G, hooks: &mut impl Hooks<N>) -> Outcome<N>
+    where
+        G: Graph<Node = N>,
+    {
+        while let Some((mut node, depth)) = self.queue.pop() {
+            if !hooks.visit(&node, depth) {
+                return Outcome::Stopped;
+            }
+            self.iterations += 1;
+            match graph.check_goal(&mut node) {
+                GraphControl::Finish => {
+                    if !graph.is_tree() {
+                        self.visited.insert(node.clone());
+                    }
+                    return Outcome::Goal(node);
+                }
+                GraphControl::Prune => hooks.prune(&node),
+                GraphControl::Continue => {
+                    let queued = self.queue.len();
+                    for neighbour in graph.neighbours_pooled(&node, &mut self.pool) {
+                        if self.visited.contains(&neighbour) {
+                            continue;
+                        }
+                        self.queue.push((neighbour, depth + 1));
                     }
-                    queue.push(neighbour);
+                    hooks.expand(&node, self.queue.len() - queued);
+                }
+            }
+            match graph.is_tree() {
+                true => self.pool.push(node),
+                false => {
+                    self.visited.insert(node);
                 }
             }
         }
-        visited.insert(node);
+        Outcome::Exhausted
+    }
+
+    /// Runs until the first goal
+    fn search(
+        &mut self,
+        graph: &impl Graph<Node = N>,
+        hooks: &mut impl Hooks<N>,
+    ) -> SearchResult<N> {
+        match self.next_goal(graph, hooks) {
+            Outcome::Goal(node) => Ok((node, self.iterations)),
+            Outcome::Exhausted => Err((SearchError::Exhausted, self.iterations)),
+            Outcome::Stopped => Err((SearchError::LimitReached, self.iterations)),
+        }
+    }
+}
+
+/// Returns solution and number of iterations
+pub fn dfs<G: Graph>(graph: G, start: G::Node) -> Result<(G::Node, usize), (SearchError, usize)> {
+    dfs_with_scratch(graph, start, &mut DfsScratch::default())
+}
+
+/// Same as [`dfs`], but gives up after visiting `max_iterations` nodes
+pub fn dfs_bounded<G: Graph>(
+    graph: G,
+    start: G::Node,
+    max_iterations: usize,
+) -> Result<(G::Node, usize), (SearchError, usize)> {
+    search(
+        graph,
+        start,
+        &mut DfsScratch::default(),
+        Some(max_iterations),
+    )
+}
+
+/// Same as [`dfs`], but reuses the queue and visited set from `scratch`
+pub fn dfs_with_scratch<G: Graph>(
+    graph: G,
+    start: G::Node,
+    scratch: &mut DfsScratch<G::Node>,
+) -> Result<(G::Node, usize), (SearchError, usize)> {
+    search(graph, start, scratch, None)
+}
+
+fn search<G: Graph>(
+    graph: G,
+    start: G::Node,
+    scratch: &mut DfsScratch<G::Node>,
+    max_iterations: Option<usize>,
+) -> Result<(G::Node, usize), (SearchError, usize)> {
+    let traversal = &mut scratch.traversal;
+    traversal.restart(start);
+    traversal.search(&graph, &mut Limit(max_iterations.unwrap_or(usize::MAX)))
+}
+
+/// Shape of a search, gathered by [`dfs_stats`]
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    /// Deepest node visited, `start` is at depth 0
+    pub max_depth: usize,
+    /// Nodes whose neighbours were queued
+    pub expanded: usize,
+    /// Neighbours queued in total
+    pub queued: usize,
+    /// Nodes that were pruned
+    pub pruned: usize,
+    /// Depth of every visited node in visiting order, only recorded when asked for
+    pub depths: Option<Vec<usize>>,
+}
+
+/// Same as [`dfs`], also returning the [`SearchStats`] of the search. The depth of each
+/// iteration is only kept when `record_depths` is true.
+#[cfg(feature = "std")]
+pub fn dfs_stats<G: Graph>(
+    graph: G,
+    start: G::Node,
+    record_depths: bool,
+) -> (SearchResult<G::Node>, SearchStats) {
+    let mut stats = SearchStats {
+        depths: record_depths.then(Vec::new),
+        ..Default::default()
+    };
+    let result = Traversal::new([start]).search(&graph, &mut stats);
+    (result, stats)
+}
+
+#[cfg(feature = "std")]
+impl<N> Hooks<N> for SearchStats {
+    fn visit(&mut self, _node: &N, depth: usize) -> bool {
+        self.max_depth = self.max_depth.max(depth);
+        if let Some(depths) = &mut self.depths {
+            depths.push(depth);
+        }
+        true
+    }
+
+    fn prune(&mut self, _node: &N) {
+        self.pruned += 1;
+    }
+
+    fn expand(&mut self, _node: &N, queued: usize) {
+        self.expanded += 1;
+        self.queued += queued;
+    }
+}
+
+/// Counts the solutions reachable from `start`, stopping once `limit` are found.
+/// Returns the count and the number of iterations.
+pub fn dfs_count<G: Graph>(graph: G, start: G::Node, limit: usize) -> (usize, usize) {
+    let mut traversal = Traversal::new([start]);
+    let mut solutions = 0;
+    while solutions < limit {
+        match traversal.next_goal(&graph, &mut ()) {
+            Outcome::Goal(_) => solutions += 1,
+            Outcome::Exhausted | Outcome::Stopped => break,
+        }
+    }
+    (solutions, traversal.iterations)
+}
+
+/// Iterator over the solutions reachable from a start node, see [`dfs_iter`]
+pub struct DfsIter<G: Graph> {
+    graph: G,
+    traversal: Traversal<G::Node>,
+}
+
+/// Lazy version of [`dfs`], each call to `next` resumes the search until the next solution
+pub fn dfs_iter<G: Graph>(graph: G, start: G::Node) -> DfsIter<G> {
+    DfsIter {
+        graph,
+        traversal: Traversal::new([start]),
+    }
+}
+
+impl<G: Graph> Iterator for DfsIter<G> {
+    type Item = G::Node;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.traversal.next_goal(&self.graph, &mut ()) {
+            Outcome::Goal(node) => Some(node),
+            Outcome::Exhausted | Outcome::Stopped => None,
+        }
+    }
+}
+
+/// Like [`dfs`], but when there is no solution returns the best scored node that was not
+/// pruned. Returns the node, whether it is a solution and the number of iterations.
+pub fn dfs_best<G, F>(graph: G, start: G::Node, score: F) -> (G::Node, bool, usize)
+where
+    G: Graph,
+    F: Fn(&G::Node) -> usize,
+{
+    let mut best = Best {
+        best: (score(&start), start.clone()),
+        score,
+    };
+    match Traversal::new([start]).search(&graph, &mut best) {
+        Ok((node, iterations)) => (node, true, iterations),
+        Err((_, iterations)) => (best.best.1, false, iterations),
+    }
+}
+
+/// Keeps the best scored node that was expanded, see [`dfs_best`]
+struct Best<N, F> {
+    best: (usize, N),
+    score: F,
+}
+
+impl<N: Clone, F: Fn(&N) -> usize> Hooks<N> for Best<N, F> {
+    fn expand(&mut self, node: &N, _queued: usize) {
+        let score = (self.score)(node);
+        if score > self.best.0 {
+            self.best = (score, node.clone());
+        }
     }
-    Err(("No solution found :C".to_string(), iterations))
 }
 
 /// Returns solution and number of iterations
-pub fn dfs_parallel<G>(graph: G, start: G::Node) -> Result<(G::Node, usize), (String, usize)>
+#[cfg(feature = "std")]
+pub fn dfs_parallel<G>(graph: G, start: G::Node) -> Result<(G::Node, usize), (SearchError, usize)>
 where
     G: Graph + Clone + Send + 'static,
     G::Node: Send + 'static,
@@ -108,8 +487,7 @@ where
                         debug!("[Handler {i}] current_tasks==0, stopping the solver...");
                         finished.fetch_or(true, Ordering::SeqCst);
                         let i = iterations.load(Ordering::SeqCst);
-                        s.send(Err(("No solution found :C".to_string(), i)))
-                            .unwrap();
+                        s.send(Err((SearchError::Exhausted, i))).unwrap();
                         break;
                     }
                 }
@@ -120,3 +498,171 @@ where
 
     r.recv().unwrap()
 }
+
+/// Parallel search where the branches of `start` are dealt round-robin to the workers and
+/// each worker explores its own subtrees sequentially until it finds a solution. Workers
+/// don't steal work or stop each other, so the same input always visits the same nodes:
+/// useful for reproducible benchmarks. Returns the solution of the first worker (in order)
+/// that found one and the total number of iterations.
+#[cfg(feature = "std")]
+pub fn dfs_parallel_round_robin<G>(
+    graph: G,
+    mut start: G::Node,
+) -> Result<(G::Node, usize), (SearchError, usize)>
+where
+    G: Graph + Clone + Send,
+    G::Node: Send,
+{
+    match graph.check_goal(&mut start) {
+        GraphControl::Finish => return Ok((start, 1)),
+        GraphControl::Prune => return Err((SearchError::Exhausted, 1)),
+        GraphControl::Continue => {}
+    }
+    let cpus = num_cpus::get();
+    let mut assigned = (0..cpus).map(|_| Vec::new()).collect::<Vec<_>>();
+    for (k, neighbour) in graph.neighbours(&start).into_iter().enumerate() {
+        assigned[k % cpus].push(neighbour);
+    }
+
+    let results = crossbeam::scope(|scope| {
+        let handles = assigned
+            .into_iter()
+            .enumerate()
+            .map(|(i, roots)| {
+                let graph = graph.clone();
+                scope.spawn(move |_| {
+                    debug!("[Worker {i}] Started with {} roots", roots.len());
+                    let mut traversal = Traversal::new(roots);
+                    match traversal.next_goal(&graph, &mut ()) {
+                        Outcome::Goal(node) => (Some(node), traversal.iterations),
+                        Outcome::Exhausted | Outcome::Stopped => (None, traversal.iterations),
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>()
+    })
+    .unwrap();
+
+    let iterations = 1 + results.iter().map(|(_, i)| i).sum::<usize>();
+    match results.into_iter().find_map(|(solution, _)| solution) {
+        Some(solution) => Ok((solution, iterations)),
+        None => Err((SearchError::Exhausted, iterations)),
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    /// Grid maze where `#` is a wall, searching from the top left to the bottom right corner
+    #[derive(Clone)]
+    struct Maze {
+        cells: Vec<Vec<bool>>,
+    }
+
+    impl Maze {
+        fn new(text: &str) -> Self {
+            let cells = text
+                .lines()
+                .map(|line| line.chars().map(|c| c != '#').collect())
+                .collect();
+            Self { cells }
+        }
+
+        fn goal(&self) -> (usize, usize) {
+            (self.cells.len() - 1, self.cells[0].len() - 1)
+        }
+    }
+
+    impl Graph for Maze {
+        type Node = (usize, usize);
+
+        fn neighbours(&self, &(i, j): &Self::Node) -> Vec<Self::Node> {
+            [(0, 1), (1, 0), (0, -1), (-1, 0)]
+                .into_iter()
+                .filter_map(|(di, dj)| {
+                    let i = i.checked_add_signed(di)?;
+                    let j = j.checked_add_signed(dj)?;
+                    self.cells.get(i)?.get(j)?.then_some((i, j))
+                })
+                .collect()
+        }
+
+        fn check_goal(&self, node: &mut Self::Node) -> GraphControl {
+            if *node == self.goal() {
+                GraphControl::Finish
+            } else {
+                GraphControl::Continue
+            }
+        }
+    }
+
+    const OPEN: &str = "\
+.....#....
+.###.#.##.
+.#...#..#.
+.#.#####..
+.#......#.
+.######.#.
+......#.#.
+.####.#...";
+
+    #[test]
+    fn dfs_parallel_works_on_other_graphs() {
+        let maze = Maze::new(OPEN);
+        let goal = maze.goal();
+        let (node, iterations) = dfs(maze.clone(), (0, 0)).unwrap();
+        assert_eq!(node, goal);
+        for _ in 0..10 {
+            let (node, parallel_iterations) = dfs_parallel(maze.clone(), (0, 0)).unwrap();
+            println!("Iterations: {iterations} (sequential) {parallel_iterations} (parallel)");
+            assert_eq!(node, goal);
+            let (node, _) = dfs_parallel_round_robin(maze.clone(), (0, 0)).unwrap();
+            assert_eq!(node, goal);
+        }
+    }
+
+    #[test]
+    fn dfs_parallel_exhausts_other_graphs() {
+        // Walls around the goal
+        let closed = OPEN.replace("#.#.\n", "#.##\n").replace("#...", "#.#.");
+        let maze = Maze::new(&closed);
+        let (err, iterations) = dfs(maze.clone(), (0, 0)).unwrap_err();
+        assert_eq!(err, SearchError::Exhausted);
+        for _ in 0..10 {
+            let (err, parallel_iterations) = dfs_parallel(maze.clone(), (0, 0)).unwrap_err();
+            println!("Iterations: {iterations} (sequential) {parallel_iterations} (parallel)");
+            assert_eq!(err, SearchError::Exhausted);
+            assert!(parallel_iterations >= iterations);
+            let (err, _) = dfs_parallel_round_robin(maze.clone(), (0, 0)).unwrap_err();
+            assert_eq!(err, SearchError::Exhausted);
+        }
+    }
+
+    #[test]
+    fn searches_share_the_traversal() {
+        let maze = Maze::new(OPEN);
+        let goal = maze.goal();
+        let (node, iterations) = dfs(maze.clone(), (0, 0)).unwrap();
+        assert_eq!(node, goal);
+        let (result, stats) = dfs_stats(maze.clone(), (0, 0), true);
+        assert_eq!(result, Ok((goal, iterations)));
+        assert_eq!(stats.depths.map(|depths| depths.len()), Some(iterations));
+        assert_eq!(stats.expanded, iterations - 1);
+        assert_eq!(
+            dfs_best(maze.clone(), (0, 0), |_| 0),
+            (goal, true, iterations)
+        );
+        assert_eq!(dfs_count(maze.clone(), (0, 0), 1), (1, iterations));
+        assert_eq!(
+            dfs_iter(maze.clone(), (0, 0)).collect::<Vec<_>>(),
+            vec![goal]
+        );
+        let limited = dfs_bounded(maze, (0, 0), iterations - 1);
+        assert_eq!(limited, Err((SearchError::LimitReached, iterations - 1)));
+    }
+}
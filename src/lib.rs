@@ -1,6 +1,53 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+mod bitboard;
+#[cfg(feature = "alloc")]
+mod generator;
+#[cfg(feature = "alloc")]
 mod graph;
+#[cfg(feature = "alloc")]
+mod rng;
+#[cfg(feature = "alloc")]
 mod solver;
+#[cfg(feature = "alloc")]
 mod sudoku;
 
-pub use solver::{solve_sudoku, solve_sudoku_parallel};
-pub use sudoku::Sudoku;
+/// What the std prelude would bring into scope, for the modules that also build with `alloc`
+#[cfg(feature = "alloc")]
+mod prelude {
+    pub use alloc::{
+        boxed::Box,
+        format,
+        string::{String, ToString},
+        vec,
+        vec::Vec,
+    };
+}
+
+pub use bitboard::solve_flat;
+#[cfg(feature = "alloc")]
+pub use generator::{carve_to_difficulty, minimize};
+#[cfg(feature = "alloc")]
+pub use rng::{Rng, SplitMix64};
+#[cfg(feature = "alloc")]
+pub use solver::{
+    bifurcation_points, count_solutions, cross_check, first_guess_point, rate_difficulty,
+    render_search_profile, solutions, solve_best_effort, solve_classified, solve_frames,
+    solve_limited_guesses, solve_logic_only, solve_many, solve_region, solve_report, solve_sudoku,
+    solve_sudoku_bounded, solve_sudoku_on_backtrack, solve_sudoku_recursive, solve_sudoku_verbose,
+    solve_sudoku_with_order, solve_until, solve_with_trace, stats_to_json, Difficulty, MoveKind,
+    SolveError, SolveReport, SolveStats, TraceEntry,
+};
+#[cfg(feature = "std")]
+pub use solver::{
+    compare_solvers, is_unique_parallel, iteration_difficulty, solve_sudoku_parallel,
+    solve_sudoku_parallel_round_robin, solve_sudoku_stats, SolverComparison,
+};
+#[cfg(feature = "alloc")]
+pub use sudoku::{
+    BoxShape, ConjugatePair, Elimination, Item, ObserverFn, PuzzleError, Sudoku, SudokuBuilder,
+    Symmetry, Technique, UnitKind, XWing,
+};
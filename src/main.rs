@@ -1,15 +1,18 @@
 use colored::Colorize;
 use itertools::Itertools;
 use log::info;
-use std::env;
+use std::{env, fs, io::Read, path::Path};
 use sudoku_solver::{solve_sudoku, solve_sudoku_parallel, Sudoku};
 
 fn main() {
     env_logger::init();
     info!("Starting...");
 
-    let args = env::args().skip(1).collect_vec();
-    let (parallel, text) = match args.len() {
+    let mut args = env::args().skip(1).collect_vec();
+    let pencil = args.iter().any(|arg| arg == "--pencil");
+    let quiet = args.iter().any(|arg| arg == "--quiet");
+    args.retain(|arg| arg != "--pencil" && arg != "--quiet");
+    let (parallel, input) = match args.len() {
         0 => {
             eprintln!("No input found");
             std::process::exit(1);
@@ -22,7 +25,31 @@ fn main() {
         }
     };
 
+    let text = match read_input(&input) {
+        Ok(text) => text,
+        Err(err) => {
+            eprintln!("{}", err.red());
+            std::process::exit(1);
+        }
+    };
+
     match Sudoku::from_text(&text) {
+        Ok(mut board) if pencil => {
+            board.compute_guesses();
+            print!("{}", board.to_candidate_grid_string());
+        }
+        Ok(board) if quiet => {
+            // Only the solution as a single line of digits, or nothing and exit code 1
+            let res = if parallel {
+                solve_sudoku_parallel(board)
+            } else {
+                solve_sudoku(board)
+            };
+            match res {
+                Ok((solution, _)) => println!("{}", solution.to_flat().iter().join("")),
+                Err(_) => std::process::exit(1),
+            }
+        }
         Ok(board) => {
             println!("Input:\n{board}");
             let res = if parallel {
@@ -41,8 +68,28 @@ fn main() {
                 }
             }
         }
+        Err(err) if quiet => {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
         Err(err) => {
             println!("{}", err.red());
         }
     }
 }
+
+/// `-` reads the board from stdin, a path to an existing file reads that file,
+/// anything else is taken as the board itself
+fn read_input(input: &str) -> Result<String, String> {
+    if input == "-" {
+        let mut text = String::new();
+        std::io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|err| format!("Couldn't read stdin: {err}"))?;
+        Ok(text)
+    } else if Path::new(input).is_file() {
+        fs::read_to_string(input).map_err(|err| format!("Couldn't read {input}: {err}"))
+    } else {
+        Ok(input.to_string())
+    }
+}
@@ -1,22 +1,158 @@
+#[cfg(feature = "std")]
+use crate::graph::{dfs_parallel, dfs_parallel_round_robin, dfs_stats};
 use crate::{
-    graph::{dfs, dfs_parallel, Graph, GraphControl},
-    sudoku::{Sudoku, SudokuState},
+    bitboard::solve_flat,
+    graph::{
+        dfs, dfs_best, dfs_bounded, dfs_count, dfs_iter, dfs_with_scratch, DfsScratch, Graph,
+        GraphControl, SearchError,
+    },
+    prelude::*,
+    sudoku::{Elimination, Item, Sudoku, SudokuState, Technique},
 };
+use core::{cell::Cell, time::Duration};
+#[cfg(feature = "std")]
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SolveError {
+    NoSolution,
+    /// The iteration budget ran out before finding a solution
+    BudgetExceeded,
+    /// Solving needs more guesses than allowed
+    TooManyGuesses,
+    /// A recursive search went deeper than allowed
+    DepthExceeded,
+}
+
+impl core::fmt::Display for SolveError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SolveError::NoSolution => write!(f, "No solution found :C"),
+            SolveError::BudgetExceeded => write!(f, "Iteration budget exceeded"),
+            SolveError::TooManyGuesses => write!(f, "Too many guesses needed"),
+            SolveError::DepthExceeded => write!(f, "Maximum search depth exceeded"),
+        }
+    }
+}
+
+impl core::error::Error for SolveError {}
+
+impl From<SearchError> for SolveError {
+    fn from(err: SearchError) -> Self {
+        match err {
+            SearchError::Exhausted => SolveError::NoSolution,
+            SearchError::LimitReached => SolveError::BudgetExceeded,
+        }
+    }
+}
+
+fn search_error((err, iterations): (SearchError, usize)) -> (SolveError, usize) {
+    (err.into(), iterations)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveKind {
+    /// Forced by the board, placed while computing guesses
+    Deduced,
+    /// Chosen when branching the search
+    Guessed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub cell: (usize, usize),
+    pub value: u16,
+    pub kind: MoveKind,
+}
+
+#[derive(Debug, Clone)]
+pub struct SolveReport {
+    pub solution: Sudoku,
+    pub iterations: usize,
+    /// Candidates removed by logic techniques before searching
+    pub eliminations: Vec<Elimination>,
+}
+
+/// Sequential and parallel runs of the same board, see `compare_solvers`
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SolverComparison {
+    pub seq_iters: usize,
+    pub par_iters: usize,
+    pub seq_time: Duration,
+    pub par_time: Duration,
+    /// Both found the same solution, or both found none
+    pub solutions_match: bool,
+}
+
+/// Statistics of a search, see `solve_sudoku_stats`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolveStats {
+    pub iterations: usize,
+    pub duration: Duration,
+    /// Number of placements (guessed or forced) on the deepest branch explored
+    pub max_depth: usize,
+    /// Average number of children of the expanded nodes
+    pub branching_factor: f64,
+    /// Nodes dropped because they had a cell without options
+    pub pruned: usize,
+    /// Search depth at each iteration, when requested. See `render_search_profile`.
+    pub depth_profile: Option<Vec<usize>>,
+}
+
+/// Rating based on the hardest logic technique needed to solve a board
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Difficulty {
+    /// Naked singles
+    Easy,
+    /// Hidden singles
+    Medium,
+    /// Naked pairs and triples, X-Wings
+    Hard,
+    /// Swordfish, only when no X-Wing makes the same eliminations
+    Expert,
+    /// Logic alone is not enough, it needs guessing
+    Extreme,
+}
 
 #[derive(Clone)]
-struct SudokuSolver;
+struct SudokuSolver<const N: usize = 9>;
 
-impl Graph for SudokuSolver {
-    type Node = Sudoku;
+impl<const N: usize> Graph for SudokuSolver<N> {
+    type Node = Sudoku<N>;
 
     fn neighbours(&self, node: &Self::Node) -> Vec<Self::Node> {
+        self.neighbours_pooled(node, &mut Vec::new())
+    }
+
+    fn check_goal(&self, node: &mut Self::Node) -> GraphControl {
+        node.compute_guesses();
+        match node.state {
+            SudokuState::Invalid => GraphControl::Prune,
+            SudokuState::Solved => GraphControl::Finish,
+            _ => GraphControl::Continue,
+        }
+    }
+
+    /// Each branch is a board from the pool overwritten with `node` (reusing its candidates'
+    /// allocations), a new clone only when the pool is empty
+    fn neighbours_pooled(&self, node: &Self::Node, pool: &mut Vec<Self::Node>) -> Vec<Self::Node> {
         match node.state {
             SudokuState::Unknown | SudokuState::Solved => unreachable!(),
             SudokuState::HaveGuesses((i, j)) => node
                 .get_guesses(i, j)
                 .into_iter()
                 .map(|guess| {
-                    let mut new_node = node.clone();
+                    let mut new_node = match pool.pop() {
+                        Some(mut board) => {
+                            board.clone_from(node);
+                            board
+                        }
+                        None => node.clone(),
+                    };
                     new_node.set(i, j, guess);
                     new_node
                 })
@@ -25,9 +161,301 @@ impl Graph for SudokuSolver {
         }
     }
 
+    /// Branches differ in the guessed cell, so no board is reached twice
+    fn is_tree(&self) -> bool {
+        true
+    }
+}
+
+/// Implements `PartialEq`, `Eq` and `Hash` for search nodes that wrap a `board` with extra
+/// data, looking only at the board: nodes reaching the same board are the same node
+macro_rules! impl_board_node {
+    ($($node:ty),*) => {$(
+        impl PartialEq for $node {
+            fn eq(&self, other: &Self) -> bool {
+                self.board == other.board
+            }
+        }
+
+        impl Eq for $node {}
+
+        impl core::hash::Hash for $node {
+            fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+                self.board.hash(state);
+            }
+        }
+    )*};
+}
+
+impl_board_node!(TracedBoard, BranchedBoard, GuessedBoard);
+
+/// Search node that remembers the placements that led to it
+#[derive(Clone)]
+struct TracedBoard {
+    board: Sudoku,
+    trace: Vec<TraceEntry>,
+}
+
+struct TracingSolver;
+
+impl Graph for TracingSolver {
+    type Node = TracedBoard;
+
+    fn neighbours(&self, node: &Self::Node) -> Vec<Self::Node> {
+        let cell = match node.board.state {
+            SudokuState::HaveGuesses(cell) => cell,
+            _ => return Vec::new(),
+        };
+        let neighbours = SudokuSolver.neighbours(&node.board);
+        // A branch with a single option is still forced by the board
+        let kind = if neighbours.len() == 1 {
+            MoveKind::Deduced
+        } else {
+            MoveKind::Guessed
+        };
+        neighbours
+            .into_iter()
+            .filter_map(|board| {
+                let value = match board.get(cell.0, cell.1) {
+                    Item::Number(x) => *x,
+                    _ => return None,
+                };
+                let mut trace = node.trace.clone();
+                trace.push(TraceEntry { cell, value, kind });
+                Some(TracedBoard { board, trace })
+            })
+            .collect()
+    }
+
     fn check_goal(&self, node: &mut Self::Node) -> GraphControl {
-        node.compute_guesses();
-        match node.state {
+        let before = node.board.to_flat();
+        node.board.propagate();
+        let control = match node.board.state {
+            SudokuState::Invalid => GraphControl::Prune,
+            SudokuState::Solved => GraphControl::Finish,
+            _ => GraphControl::Continue,
+        };
+        let deduced = before
+            .into_iter()
+            .zip(node.board.to_flat())
+            .enumerate()
+            .filter(|&(_, (old, new))| old == 0 && new != 0)
+            .map(|(k, (_, new))| TraceEntry {
+                cell: (k / 9, k % 9),
+                value: new as u16,
+                kind: MoveKind::Deduced,
+            });
+        node.trace.extend(deduced);
+        control
+    }
+}
+
+/// Branches on the first cell of `order` that still has guesses, or on the cell with the
+/// fewest guesses when none of the listed cells is left
+#[derive(Clone)]
+struct OrderedSolver {
+    order: Vec<(usize, usize)>,
+}
+
+impl OrderedSolver {
+    fn branch_cell(&self, board: &Sudoku) -> Option<(usize, usize)> {
+        let has_guesses = |&(i, j): &(usize, usize)| matches!(board.get(i, j), Item::Guesses(_));
+        self.order.iter().copied().find(has_guesses).or_else(|| {
+            (0..9)
+                .flat_map(|i| (0..9).map(move |j| (i, j)))
+                .filter(has_guesses)
+                .min_by_key(|&(i, j)| board.get_guesses(i, j).len())
+        })
+    }
+}
+
+impl Graph for OrderedSolver {
+    type Node = Sudoku;
+
+    fn neighbours(&self, node: &Self::Node) -> Vec<Self::Node> {
+        SudokuSolver.neighbours(node)
+    }
+
+    fn check_goal(&self, node: &mut Self::Node) -> GraphControl {
+        let control = SudokuSolver.check_goal(node);
+        if let GraphControl::Continue = control {
+            if let Some(cell) = self.branch_cell(node) {
+                node.state = SudokuState::HaveGuesses(cell);
+            }
+        }
+        control
+    }
+}
+
+/// Stops exploring once `found` reaches two, shared by the threads of `is_unique_parallel`
+#[cfg(feature = "std")]
+struct UniquenessSolver<'a> {
+    found: &'a AtomicUsize,
+}
+
+#[cfg(feature = "std")]
+impl Graph for UniquenessSolver<'_> {
+    type Node = Sudoku;
+
+    fn neighbours(&self, node: &Self::Node) -> Vec<Self::Node> {
+        SudokuSolver.neighbours(node)
+    }
+
+    fn check_goal(&self, node: &mut Self::Node) -> GraphControl {
+        if self.found.load(Ordering::SeqCst) >= 2 {
+            return GraphControl::Prune;
+        }
+        SudokuSolver.check_goal(node)
+    }
+}
+
+/// Finishes as soon as `goal` holds on a valid board
+struct GoalSolver<F> {
+    goal: F,
+}
+
+impl<F: Fn(&Sudoku) -> bool> Graph for GoalSolver<F> {
+    type Node = Sudoku;
+
+    fn neighbours(&self, node: &Self::Node) -> Vec<Self::Node> {
+        SudokuSolver.neighbours(node)
+    }
+
+    fn check_goal(&self, node: &mut Self::Node) -> GraphControl {
+        match SudokuSolver.check_goal(node) {
+            GraphControl::Prune => GraphControl::Prune,
+            _ if (self.goal)(node) => GraphControl::Finish,
+            // A full board can't make more progress towards the goal
+            GraphControl::Finish => GraphControl::Prune,
+            GraphControl::Continue => GraphControl::Continue,
+        }
+    }
+}
+
+/// Only fills the cells of `region`, the rest of the board is left as it is
+struct RegionSolver<'a> {
+    region: &'a [(usize, usize)],
+}
+
+impl RegionSolver<'_> {
+    /// Empty region cell with the fewest candidates
+    fn branch_cell(&self, node: &Sudoku) -> Option<(usize, usize)> {
+        self.region
+            .iter()
+            .copied()
+            .filter(|&(i, j)| !matches!(node.get(i, j), Item::Number(_)))
+            .min_by_key(|&(i, j)| node.get_guesses(i, j).len())
+    }
+}
+
+impl Graph for RegionSolver<'_> {
+    type Node = Sudoku;
+
+    fn neighbours(&self, node: &Self::Node) -> Vec<Self::Node> {
+        let Some((i, j)) = self.branch_cell(node) else {
+            return Vec::new();
+        };
+        node.get_guesses(i, j)
+            .into_iter()
+            .map(|guess| {
+                let mut new_node = node.clone();
+                new_node.set(i, j, guess);
+                new_node
+            })
+            .collect()
+    }
+
+    fn check_goal(&self, node: &mut Self::Node) -> GraphControl {
+        match self.branch_cell(node) {
+            None => GraphControl::Finish,
+            Some((i, j)) if node.get_guesses(i, j).is_empty() => GraphControl::Prune,
+            Some(_) => GraphControl::Continue,
+        }
+    }
+}
+
+/// Calls `on_backtrack` with the number of filled cells of every pruned board
+struct BacktrackSolver<F> {
+    on_backtrack: F,
+}
+
+impl<F: Fn(usize)> Graph for BacktrackSolver<F> {
+    type Node = Sudoku;
+
+    fn neighbours(&self, node: &Self::Node) -> Vec<Self::Node> {
+        SudokuSolver.neighbours(node)
+    }
+
+    fn check_goal(&self, node: &mut Self::Node) -> GraphControl {
+        let control = SudokuSolver.check_goal(node);
+        if let GraphControl::Prune = control {
+            (self.on_backtrack)(node.filled_count());
+        }
+        control
+    }
+}
+
+/// Search node that keeps the last board it branched from, with its guesses
+#[derive(Clone)]
+struct BranchedBoard {
+    board: Sudoku,
+    last_branch: Option<Sudoku>,
+}
+
+struct BranchingSolver;
+
+impl Graph for BranchingSolver {
+    type Node = BranchedBoard;
+
+    fn neighbours(&self, node: &Self::Node) -> Vec<Self::Node> {
+        SudokuSolver
+            .neighbours(&node.board)
+            .into_iter()
+            .map(|board| BranchedBoard {
+                board,
+                last_branch: Some(node.board.clone()),
+            })
+            .collect()
+    }
+
+    fn check_goal(&self, node: &mut Self::Node) -> GraphControl {
+        SudokuSolver.check_goal(&mut node.board)
+    }
+}
+
+/// Search node that counts the guesses that led to it
+#[derive(Clone)]
+struct GuessedBoard {
+    board: Sudoku,
+    guesses: usize,
+}
+
+/// Uses logic techniques at every node and stops branching after `max_guesses` guesses,
+/// setting `cut` when a branch was dropped because of the limit
+struct LimitedGuessSolver<'a> {
+    max_guesses: usize,
+    cut: &'a Cell<bool>,
+}
+
+impl Graph for LimitedGuessSolver<'_> {
+    type Node = GuessedBoard;
+
+    fn neighbours(&self, node: &Self::Node) -> Vec<Self::Node> {
+        let children = SudokuSolver.neighbours(&node.board);
+        let guesses = node.guesses + usize::from(children.len() > 1);
+        if guesses > self.max_guesses {
+            self.cut.set(true);
+            return Vec::new();
+        }
+        children
+            .into_iter()
+            .map(|board| GuessedBoard { board, guesses })
+            .collect()
+    }
+
+    fn check_goal(&self, node: &mut Self::Node) -> GraphControl {
+        node.board.propagate();
+        match node.board.state {
             SudokuState::Invalid => GraphControl::Prune,
             SudokuState::Solved => GraphControl::Finish,
             _ => GraphControl::Continue,
@@ -35,17 +463,485 @@ impl Graph for SudokuSolver {
     }
 }
 
-pub fn solve_sudoku(board: Sudoku) -> Result<(Sudoku, usize), (String, usize)> {
+/// Applies logic techniques and only calls `search` when they are not enough, the logic
+/// pass counts as one iteration. The candidates eliminated by the techniques are dropped from
+/// the solution, so it is the same board a plain search finds.
+fn logic_then_search<const N: usize, F>(
+    board: Sudoku<N>,
+    search: F,
+) -> Result<(Sudoku<N>, usize), (SolveError, usize)>
+where
+    F: FnOnce(Sudoku<N>) -> Result<(Sudoku<N>, usize), (SearchError, usize)>,
+{
+    let board = solve_logic_only(board);
+    let (mut solution, iterations) = match board.state {
+        SudokuState::Solved => (board, 1),
+        SudokuState::Invalid => return Err((SolveError::NoSolution, 1)),
+        _ => search(board)
+            .map(|(solution, iterations)| (solution, iterations + 1))
+            .map_err(|(err, iterations)| search_error((err, iterations + 1)))?,
+    };
+    solution.clear_eliminations();
+    Ok((solution, iterations))
+}
+
+/// Returns solution and number of iterations. Puzzles that logic techniques solve take a
+/// single iteration, the rest fall back to searching.
+pub fn solve_sudoku<const N: usize>(
+    board: Sudoku<N>,
+) -> Result<(Sudoku<N>, usize), (SolveError, usize)> {
+    logic_then_search(board, |board| dfs(SudokuSolver, board))
+}
+
+/// Solves the board and tells whether guessing was needed, that is whether logic techniques
+/// stalled before the solution and the search had to branch
+pub fn solve_classified(board: Sudoku) -> Result<(Sudoku, bool), SolveError> {
+    let guessed = Cell::new(false);
+    let (solution, _) = logic_then_search(board, |board| {
+        guessed.set(true);
+        dfs(SudokuSolver, board)
+    })
+    .map_err(|(err, _)| err)?;
+    Ok((solution, guessed.get()))
+}
+
+#[cfg(feature = "std")]
+pub fn solve_sudoku_parallel(board: Sudoku) -> Result<(Sudoku, usize), (SolveError, usize)> {
+    let graph = SudokuSolver;
+    dfs_parallel(graph, board).map_err(search_error)
+}
+
+/// Solves the board with `solve_sudoku` and `solve_sudoku_parallel`, timing both
+#[cfg(feature = "std")]
+pub fn compare_solvers(board: Sudoku) -> SolverComparison {
+    let start = Instant::now();
+    let sequential = solve_sudoku(board.clone());
+    let seq_time = start.elapsed();
+    let start = Instant::now();
+    let parallel = solve_sudoku_parallel(board);
+    let par_time = start.elapsed();
+    let iterations = |result: &Result<(Sudoku, usize), (SolveError, usize)>| match result {
+        Ok((_, iterations)) | Err((_, iterations)) => *iterations,
+    };
+    SolverComparison {
+        seq_iters: iterations(&sequential),
+        par_iters: iterations(&parallel),
+        seq_time,
+        par_time,
+        solutions_match: match (&sequential, &parallel) {
+            (Ok((expected, _)), Ok((solution, _))) => expected.solution_eq(solution),
+            (Err(_), Err(_)) => true,
+            _ => false,
+        },
+    }
+}
+
+/// Same as `count_solutions(board, 2) == 1`, but each candidate of the first branch is
+/// searched in its own thread. Every thread stops as soon as two solutions are found overall.
+#[cfg(feature = "std")]
+pub fn is_unique_parallel(board: &Sudoku) -> bool {
+    let mut root = board.clone();
+    match SudokuSolver.check_goal(&mut root) {
+        GraphControl::Finish => return true,
+        GraphControl::Prune => return false,
+        GraphControl::Continue => {}
+    }
+    let found = &AtomicUsize::new(0);
+    crossbeam::scope(|scope| {
+        for child in SudokuSolver.neighbours(&root) {
+            let graph = UniquenessSolver { found };
+            scope.spawn(move |_| {
+                for _ in dfs_iter(graph, child) {
+                    if found.fetch_add(1, Ordering::SeqCst) + 1 >= 2 {
+                        break;
+                    }
+                }
+            });
+        }
+    })
+    .unwrap();
+    found.load(Ordering::SeqCst) == 1
+}
+
+/// Lazily yields the solutions of the board, the search only advances when the next one is
+/// requested
+pub fn solutions(board: Sudoku) -> impl Iterator<Item = Sudoku> {
+    dfs_iter(SudokuSolver, board)
+}
+
+/// Number of solutions of the board, counting stops at `limit`
+pub fn count_solutions<const N: usize>(board: Sudoku<N>, limit: usize) -> usize {
+    let graph = SudokuSolver;
+    dfs_count(graph, board, limit).0
+}
+
+/// Parallel solver that deals the first branches round-robin to the workers instead of
+/// sharing a queue, so the explored subtrees and iteration count are the same on every run.
+#[cfg(feature = "std")]
+pub fn solve_sudoku_parallel_round_robin(
+    board: Sudoku,
+) -> Result<(Sudoku, usize), (SolveError, usize)> {
     let graph = SudokuSolver;
-    dfs(graph, board)
+    dfs_parallel_round_robin(graph, board).map_err(search_error)
+}
+
+/// Like `solve_sudoku`, but when branching picks the earliest cell of `order` that is still
+/// empty, falling back to the cell with the fewest guesses for unlisted cells
+pub fn solve_sudoku_with_order(
+    board: Sudoku,
+    order: &[(usize, usize)],
+) -> Result<(Sudoku, usize), (SolveError, usize)> {
+    let graph = OrderedSolver {
+        order: order.to_vec(),
+    };
+    dfs(graph, board).map_err(search_error)
+}
+
+/// Recursive backtracking search that fails with `SolveError::DepthExceeded` instead of
+/// going more than `max_depth` placements deep, so it can't overflow the stack. Prefer
+/// `solve_sudoku`, which keeps its stack on the heap.
+pub fn solve_sudoku_recursive(
+    board: Sudoku,
+    max_depth: usize,
+) -> Result<(Sudoku, usize), (SolveError, usize)> {
+    let mut iterations = 0;
+    match backtrack(board, 0, max_depth, &mut iterations) {
+        Ok(solution) => Ok((solution, iterations)),
+        Err(err) => Err((err, iterations)),
+    }
+}
+
+fn backtrack(
+    mut node: Sudoku,
+    depth: usize,
+    max_depth: usize,
+    iterations: &mut usize,
+) -> Result<Sudoku, SolveError> {
+    *iterations += 1;
+    match SudokuSolver.check_goal(&mut node) {
+        GraphControl::Finish => return Ok(node),
+        GraphControl::Prune => return Err(SolveError::NoSolution),
+        GraphControl::Continue if depth >= max_depth => return Err(SolveError::DepthExceeded),
+        GraphControl::Continue => {}
+    }
+    let mut exceeded = false;
+    // Same order as `dfs`, which pops the last neighbour first
+    for child in SudokuSolver.neighbours(&node).into_iter().rev() {
+        match backtrack(child, depth + 1, max_depth, iterations) {
+            Ok(solution) => return Ok(solution),
+            Err(SolveError::DepthExceeded) => exceeded = true,
+            Err(_) => {}
+        }
+    }
+    match exceeded {
+        true => Err(SolveError::DepthExceeded),
+        false => Err(SolveError::NoSolution),
+    }
+}
+
+/// Searches for the solution (without the logic pass of `solve_sudoku`) and returns the
+/// statistics of the search along with the result. The depth of every iteration is kept in
+/// `depth_profile` when `profile` is true.
+#[cfg(feature = "std")]
+pub fn solve_sudoku_stats(
+    board: Sudoku,
+    profile: bool,
+) -> (Result<Sudoku, SolveError>, SolveStats) {
+    let start = Instant::now();
+    let (result, search) = dfs_stats(SudokuSolver, board, profile);
+    let (result, iterations) = match result {
+        Ok((solution, iterations)) => (Ok(solution), iterations),
+        Err((err, iterations)) => (Err(err.into()), iterations),
+    };
+    let stats = SolveStats {
+        iterations,
+        duration: start.elapsed(),
+        max_depth: search.max_depth,
+        branching_factor: match search.expanded {
+            0 => 0.0,
+            expanded => search.queued as f64 / expanded as f64,
+        },
+        pruned: search.pruned,
+        depth_profile: search.depths,
+    };
+    (result, stats)
+}
+
+/// ASCII sparkline of search depths, one char per sample scaled to the deepest one
+pub fn render_search_profile(samples: &[usize]) -> String {
+    const LEVELS: &[u8] = b" .:-=+*#%@";
+    let max = samples.iter().copied().max().unwrap_or(0).max(1);
+    samples
+        .iter()
+        .map(|&depth| LEVELS[depth * (LEVELS.len() - 1) / max] as char)
+        .collect()
+}
+
+/// `stats` as a JSON object, the duration is given in milliseconds
+pub fn stats_to_json(stats: &SolveStats) -> String {
+    format!(
+        "{{\"iterations\":{},\"duration_ms\":{},\"max_depth\":{},\"branching_factor\":{},\"pruned\":{}}}",
+        stats.iterations,
+        stats.duration.as_secs_f64() * 1000.0,
+        stats.max_depth,
+        stats.branching_factor,
+        stats.pruned
+    )
+}
+
+/// Searches like `solve_sudoku`, but stops at the first valid (possibly partial) board where
+/// `goal` holds
+pub fn solve_until<F>(board: Sudoku, goal: F) -> Result<(Sudoku, usize), (SolveError, usize)>
+where
+    F: Fn(&Sudoku) -> bool,
+{
+    dfs(GoalSolver { goal }, board).map_err(search_error)
+}
+
+/// Fills only the empty cells of `region`, branching on them alone and keeping the rest of the
+/// board as it is (cells outside the region stay empty). Fails right away if the placed
+/// numbers already conflict.
+pub fn solve_region(board: Sudoku, region: &[(usize, usize)]) -> Result<Sudoku, SolveError> {
+    if !board.find_conflicts().is_empty() {
+        return Err(SolveError::NoSolution);
+    }
+    let (mut solution, _) =
+        dfs(RegionSolver { region }, board).map_err(|(err, _)| SolveError::from(err))?;
+    solution.state = match solution.filled_count() {
+        81 => SudokuState::Solved,
+        _ => SudokuState::Unknown,
+    };
+    Ok(solution)
 }
 
-pub fn solve_sudoku_parallel(board: Sudoku) -> Result<(Sudoku, usize), (String, usize)> {
+/// Searches like `solve_sudoku` (without the logic pass), calling `on_backtrack` with the
+/// number of filled cells each time the search hits a dead end and backtracks
+pub fn solve_sudoku_on_backtrack<F>(
+    board: Sudoku,
+    on_backtrack: F,
+) -> Result<(Sudoku, usize), (SolveError, usize)>
+where
+    F: Fn(usize),
+{
+    dfs(BacktrackSolver { on_backtrack }, board).map_err(search_error)
+}
+
+/// Returns the solution and the last board the search branched from, which keeps the
+/// `Guesses` of that decision point. When no branching was needed both are the solution.
+pub fn solve_sudoku_verbose(board: Sudoku) -> Result<(Sudoku, Sudoku), SolveError> {
+    let start = BranchedBoard {
+        board,
+        last_branch: None,
+    };
+    let (node, _) = dfs(BranchingSolver, start).map_err(|(err, _)| SolveError::from(err))?;
+    let last_branch = node.last_branch.unwrap_or_else(|| node.board.clone());
+    Ok((node.board, last_branch))
+}
+
+/// Solves with logic techniques, branching at most `max_guesses` times along the way to the
+/// solution (0 means logic only). Fails with `SolveError::TooManyGuesses` if that is not
+/// enough, or `SolveError::NoSolution` if the board has no solution regardless of the limit.
+/// Telling both apart when the limit cut the search takes a full search for a solution.
+pub fn solve_limited_guesses(
+    board: Sudoku,
+    max_guesses: usize,
+) -> Result<(Sudoku, usize), (SolveError, usize)> {
+    let cut = Cell::new(false);
+    let graph = LimitedGuessSolver {
+        max_guesses,
+        cut: &cut,
+    };
+    let start = GuessedBoard {
+        board: board.clone(),
+        guesses: 0,
+    };
+    match dfs(graph, start) {
+        Ok((mut node, iterations)) => {
+            node.board.clear_eliminations();
+            Ok((node.board, iterations))
+        }
+        Err((_, iterations)) if cut.get() && count_solutions(board, 1) == 1 => {
+            Err((SolveError::TooManyGuesses, iterations))
+        }
+        Err((_, iterations)) => Err((SolveError::NoSolution, iterations)),
+    }
+}
+
+/// Like `solve_sudoku`, but fails with `SolveError::BudgetExceeded` after `max_iterations`
+/// nodes, the logic pass being the first one. Unlike a time limit, this gives the same result
+/// on every machine.
+pub fn solve_sudoku_bounded(
+    board: Sudoku,
+    max_iterations: usize,
+) -> Result<(Sudoku, usize), (SolveError, usize)> {
+    if max_iterations == 0 {
+        return Err((SolveError::BudgetExceeded, 0));
+    }
+    logic_then_search(board, |board| {
+        dfs_bounded(SudokuSolver, board, max_iterations.saturating_sub(1))
+    })
+}
+
+/// Returns the solution if there is one, otherwise the most filled valid board that was
+/// reached. Also returns whether the board is a solution and the number of iterations.
+pub fn solve_best_effort(board: Sudoku) -> (Sudoku, bool, usize) {
     let graph = SudokuSolver;
-    dfs_parallel(graph, board)
+    dfs_best(graph, board, Sudoku::filled_count)
 }
 
-#[cfg(test)]
+/// Returns the solution along with every placement on the path to it, in order. Like
+/// `solve_sudoku`, the solution doesn't keep the candidates eliminated by logic techniques.
+pub fn solve_with_trace(board: Sudoku) -> Result<(Sudoku, Vec<TraceEntry>), SolveError> {
+    let start = TracedBoard {
+        board,
+        trace: Vec::new(),
+    };
+    dfs(TracingSolver, start)
+        .map(|(mut node, _)| {
+            node.board.clear_eliminations();
+            (node.board, node.trace)
+        })
+        .map_err(|(err, _)| err.into())
+}
+
+/// The board after each placement of `solve_with_trace`, starting with `board` itself and
+/// ending with the solution, so every frame has one more number than the previous one.
+/// Empty if the board has no solution.
+pub fn solve_frames(board: Sudoku) -> Vec<Sudoku> {
+    let Ok((solution, trace)) = solve_with_trace(board.clone()) else {
+        return Vec::new();
+    };
+    let mut frames = vec![board];
+    for entry in trace {
+        let (i, j) = entry.cell;
+        let mut frame = frames.last().unwrap().snapshot();
+        if matches!(frame.get(i, j), Item::Number(_)) {
+            continue;
+        }
+        frame.set(i, j, entry.value);
+        frames.push(frame);
+    }
+    // The last frame holds the same numbers, the solution also has the solved state
+    if frames.len() > 1 {
+        *frames.last_mut().unwrap() = solution;
+    }
+    frames
+}
+
+/// Cells where the search of `solve_with_trace` had to guess on the way to the solution, in
+/// order. Empty if logic alone solves the board or if it has no solution.
+pub fn bifurcation_points(board: Sudoku) -> Vec<(usize, usize)> {
+    let Ok((_, trace)) = solve_with_trace(board) else {
+        return Vec::new();
+    };
+    trace
+        .into_iter()
+        .filter(|entry| entry.kind == MoveKind::Guessed)
+        .map(|entry| entry.cell)
+        .collect()
+}
+
+/// Propagates the board with logic techniques, logging every eliminated candidate,
+/// then searches the rest of the solution
+pub fn solve_report(mut board: Sudoku) -> Result<SolveReport, (SolveError, usize)> {
+    let eliminations = board.propagate();
+    let (solution, iterations) = solve_sudoku(board)?;
+    Ok(SolveReport {
+        solution,
+        iterations,
+        eliminations,
+    })
+}
+
+/// Applies logic techniques only, without guessing. The returned board is solved only if
+/// logic was enough, otherwise it holds the progress made along with the eliminated candidates.
+pub fn solve_logic_only<const N: usize>(mut board: Sudoku<N>) -> Sudoku<N> {
+    board.propagate();
+    if board.state == SudokuState::Solved {
+        board.clear_eliminations();
+    }
+    board
+}
+
+/// Solves the board with both the graph search and the bitmask solver (`solve_flat`),
+/// returning the solution if they agree or a description of the disagreement. Meant for
+/// puzzles with a unique solution, otherwise the solvers may each find a different one.
+pub fn cross_check(board: Sudoku) -> Result<Sudoku, String> {
+    let mut cells: [u8; 81] = board.to_flat().try_into().expect("boards have 81 cells");
+    let flat_solved = solve_flat(&mut cells);
+    match (solve_sudoku(board), flat_solved) {
+        (Ok((solution, _)), true) if solution.to_flat() == cells => Ok(solution),
+        (Ok((solution, _)), true) => Err(format!(
+            "Solvers found different solutions:\n{solution}\n{}",
+            Sudoku::from_flat(&cells).expect("solve_flat gives valid boards")
+        )),
+        (Ok(_), false) => Err("Only the graph search found a solution".to_string()),
+        (Err(_), true) => Err("Only the bitmask solver found a solution".to_string()),
+        (Err((err, _)), false) => Err(format!("Neither solver found a solution: {err}")),
+    }
+}
+
+/// Cell where the solver has to start guessing once logic techniques run out, `None` if
+/// logic solves the board (or shows it has no solution)
+pub fn first_guess_point(board: Sudoku) -> Option<(usize, usize)> {
+    match solve_logic_only(board).state {
+        SudokuState::HaveGuesses(cell) => Some(cell),
+        _ => None,
+    }
+}
+
+/// Iteration count that maps to the top of the `iteration_difficulty` scale
+#[cfg(feature = "std")]
+const MAX_SCORED_ITERATIONS: f64 = 100_000.0;
+
+/// Heuristic 0-100 score from the iterations `solve_sudoku` needs (log scaled, so doubling the
+/// work adds a fixed amount). Boards solved by logic alone score 0. The count depends on the
+/// solver implementation, so scores are only comparable within the same version, use
+/// `rate_difficulty` for a stable rating. Needs std for the logarithm.
+#[cfg(feature = "std")]
+pub fn iteration_difficulty(board: &Sudoku) -> f64 {
+    let iterations = match solve_sudoku(board.clone()) {
+        Ok((_, iterations)) | Err((_, iterations)) => iterations,
+    };
+    let score = (iterations.max(1) as f64).ln() / MAX_SCORED_ITERATIONS.ln() * 100.0;
+    score.min(100.0)
+}
+
+pub fn rate_difficulty(board: &Sudoku) -> Difficulty {
+    let tiers = [
+        (Technique::NakedSingle, Difficulty::Easy),
+        (Technique::HiddenSingle, Difficulty::Medium),
+        (Technique::XWing, Difficulty::Hard),
+        (Technique::Swordfish, Difficulty::Expert),
+    ];
+    tiers
+        .into_iter()
+        .find(|&(technique, _)| {
+            let mut board = board.clone();
+            board.propagate_with(technique);
+            board.is_solved()
+        })
+        .map_or(Difficulty::Extreme, |(_, difficulty)| difficulty)
+}
+
+/// Solves several boards in sequence, reusing the search storage and the pool of boards the
+/// branches are built over between them
+pub fn solve_many<I>(boards: I) -> Vec<Result<(Sudoku, usize), (SolveError, usize)>>
+where
+    I: IntoIterator<Item = Sudoku>,
+{
+    let mut scratch = DfsScratch::default();
+    boards
+        .into_iter()
+        .map(|board| {
+            logic_then_search(board, |board| {
+                dfs_with_scratch(SudokuSolver, board, &mut scratch)
+            })
+        })
+        .collect()
+}
+
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
 
@@ -86,4 +982,903 @@ mod tests {
         println!("{solved_board}");
         assert_eq!(expected_solution, solved_board);
     }
+
+    #[test]
+    fn bifurcation_points_works() {
+        let text = "1    7 9
+ 3  2   8
+  96  5
+  53  9
+ 1  8   2
+6    4
+3      1
+ 4      7
+  7   3";
+        let board = Sudoku::from_text(text).unwrap();
+        let points = bifurcation_points(board.clone());
+        println!("{points:?}");
+        assert!(points.len() > 1);
+        let distinct = points.iter().collect::<std::collections::HashSet<_>>();
+        assert_eq!(distinct.len(), points.len());
+        assert!(points
+            .iter()
+            .all(|&(i, j)| !matches!(board.get(i, j), Item::Number(_))));
+        assert_eq!(first_guess_point(board), points.first().copied());
+
+        let easy = "  3 2 6
+9  3 5  1
+  18 64
+  81 29
+7       8
+  67 82
+  26 95
+8  2 3  9
+  5 1 3";
+        assert!(bifurcation_points(Sudoku::from_text(easy).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn solve_region_works() {
+        let text = "926817345
+851394726
+473265891
+685123479
+734589162
+219746538
+568472913
+342951687
+197638254";
+        let full = Sudoku::from_text(text).unwrap();
+        let region = (3..6)
+            .flat_map(|i| (3..6).map(move |j| (i, j)))
+            .collect::<Vec<_>>();
+        let mut board = full.clone();
+        for &(i, j) in &region {
+            board.clear(i, j).unwrap();
+        }
+        let solution = solve_region(board.clone(), &region).unwrap();
+        assert!(solution.is_solved());
+        assert!(solution.solution_eq(&full));
+
+        // Cells outside the region are never filled
+        board.clear(0, 0).unwrap();
+        let solution = solve_region(board.clone(), &region).unwrap();
+        assert_eq!(*solution.get(0, 0), Item::Empty);
+        assert_eq!(solution.hamming(&full), 1);
+
+        let mut conflicting = board.clone();
+        conflicting.set(0, 0, 2);
+        assert_eq!(
+            solve_region(conflicting, &region),
+            Err(SolveError::NoSolution)
+        );
+    }
+
+    #[test]
+    fn solve_sudoku_on_backtrack_works() {
+        let text = "1    7 9
+ 3  2   8
+  96  5
+  53  9
+ 1  8   2
+6    4
+3      1
+ 4      7
+  7   3";
+        let board = Sudoku::from_text(text).unwrap();
+        let backtracks = Cell::new(0);
+        let deepest = Cell::new(0);
+        let (solution, _) = solve_sudoku_on_backtrack(board.clone(), |filled| {
+            backtracks.set(backtracks.get() + 1);
+            deepest.set(deepest.get().max(filled));
+        })
+        .unwrap();
+        println!(
+            "{} backtracks, deepest at {}",
+            backtracks.get(),
+            deepest.get()
+        );
+        assert!(solution.solution_eq(&solve_sudoku(board.clone()).unwrap().0));
+        assert!(backtracks.get() > 0);
+        assert!(deepest.get() > board.clue_count() && deepest.get() < 81);
+        let (_, stats) = solve_sudoku_stats(board, false);
+        assert_eq!(backtracks.get(), stats.pruned);
+    }
+
+    #[test]
+    fn solve_classified_works() {
+        let easy = Sudoku::from_text(
+            "  3 2 6
+9  3 5  1
+  18 64
+  81 29
+7       8
+  67 82
+  26 95
+8  2 3  9
+  5 1 3",
+        )
+        .unwrap();
+        let hard = Sudoku::from_text(
+            "1    7 9
+ 3  2   8
+  96  5
+  53  9
+ 1  8   2
+6    4
+3      1
+ 4      7
+  7   3",
+        )
+        .unwrap();
+        let (solution, guessed) = solve_classified(easy.clone()).unwrap();
+        assert!(!guessed);
+        assert!(solution.solution_eq(&solve_sudoku(easy).unwrap().0));
+        let (solution, guessed) = solve_classified(hard.clone()).unwrap();
+        assert!(guessed);
+        assert!(solution.solution_eq(&solve_sudoku(hard).unwrap().0));
+        let unsolvable = Sudoku::from_text(" 23456789\n\n\n\n1").unwrap();
+        assert_eq!(solve_classified(unsolvable), Err(SolveError::NoSolution));
+    }
+
+    #[test]
+    fn solve_frames_works() {
+        let text = "       9
+   8  1
+  7   2
+   3 7
+   51
+58149 76
+  5     2
+9 6    3
+ 2  3 54";
+        let board = Sudoku::from_text(text).unwrap();
+        let frames = solve_frames(board.clone());
+        assert_eq!(frames[0], board);
+        assert!(frames.last().unwrap().is_solved());
+        assert_eq!(frames.len(), 81 - board.clue_count() + 1);
+        for pair in frames.windows(2) {
+            let (previous, frame) = (&pair[0], &pair[1]);
+            assert_eq!(frame.clue_count(), previous.clue_count() + 1);
+            assert_eq!(frame.hamming(previous), 1);
+        }
+        let unsolvable = Sudoku::from_text(" 23456789\n\n\n\n1").unwrap();
+        assert!(solve_frames(unsolvable).is_empty());
+    }
+
+    #[test]
+    fn compare_solvers_works() {
+        let text = "1    7 9
+ 3  2   8
+  96  5
+  53  9
+ 1  8   2
+6    4
+3      1
+ 4      7
+  7   3";
+        let board = Sudoku::from_text(text).unwrap();
+        let comparison = compare_solvers(board);
+        println!("{comparison:?}");
+        assert!(comparison.solutions_match);
+        assert!(comparison.seq_iters > 1 && comparison.par_iters > 1);
+
+        let unsolvable = Sudoku::from_text(" 23456789\n\n\n\n1").unwrap();
+        assert!(compare_solvers(unsolvable).solutions_match);
+    }
+
+    #[test]
+    fn solve_many_works() {
+        let texts = [
+            " 1
+69  2  57
+    692
+  9   4
+47     2
+581 9   3
+  5  86
+ 4 2  8 1
+   6   4",
+            "926817 45
+8 139 726
+4  26 891
+6 5   47
+73  8 1 2
+2 97465 8
+    72
+ 42  1  7
+1 76 8  4",
+        ];
+        let boards = texts
+            .iter()
+            .map(|text| Sudoku::from_text(text).unwrap())
+            .collect::<Vec<_>>();
+        let results = solve_many(boards.clone());
+        assert_eq!(results.len(), boards.len());
+        for (board, result) in boards.into_iter().zip(results) {
+            assert_eq!(result, solve_sudoku(board));
+        }
+    }
+
+    #[test]
+    fn solve_best_effort_works() {
+        let text = "21
+69  2  57
+    692
+  9   4
+47     2
+581 9   3
+  5  86
+ 4 2  8 1
+   6   4";
+        let board = Sudoku::from_text(text).unwrap();
+        assert!(solve_sudoku(board.clone()).is_err());
+        let (best, solved, time) = solve_best_effort(board.clone());
+        println!("({time} iterations)\n{best}");
+        assert!(!solved);
+        assert!(best.filled_count() > board.filled_count());
+    }
+
+    #[test]
+    fn solve_with_trace_works() {
+        let text = "  3 2 6
+9  3 5  1
+  18 64
+  81 29
+7       8
+  67 82
+  26 95
+8  2 3  9
+  5 1 3";
+        let board = Sudoku::from_text(text).unwrap();
+        let (solution, trace) = solve_with_trace(board.clone()).unwrap();
+        assert_eq!(trace.len(), 81 - board.filled_count());
+        assert!(trace.iter().all(|entry| entry.kind == MoveKind::Deduced));
+        assert_eq!(Ok(solution), board.solved());
+    }
+
+    #[test]
+    fn solve_with_trace_reports_guesses() {
+        let text = "1    7 9
+ 3  2   8
+  96  5
+  53  9
+ 1  8   2
+6    4
+3      1
+ 4      7
+  7   3";
+        let mut board = Sudoku::from_text(text).unwrap();
+        let (solution, trace) = solve_with_trace(board.clone()).unwrap();
+        println!("{trace:?}");
+        assert!(trace.iter().any(|entry| entry.kind == MoveKind::Guessed));
+        // The search eliminates candidates on the way, the solution must not keep them
+        assert_eq!(solution, solve_sudoku(board.clone()).unwrap().0);
+        let moves = trace
+            .iter()
+            .map(|entry| (entry.cell.0, entry.cell.1, entry.value))
+            .collect::<Vec<_>>();
+        board.apply_moves(&moves).unwrap();
+        assert_eq!(board.to_flat(), solution.to_flat());
+    }
+
+    #[test]
+    fn solve_report_works() {
+        let text = "  3
+456
+789";
+        let board = Sudoku::from_text(text).unwrap();
+        let report = solve_report(board.clone()).unwrap();
+        println!("{}", report.solution);
+        assert!(report.solution.is_solved());
+        assert_eq!(report.eliminations.len(), 12);
+        assert!(report
+            .eliminations
+            .iter()
+            .all(|e| e.technique == Technique::NakedPair && e.cell.0 == 0));
+    }
+
+    #[test]
+    fn count_solutions_works() {
+        let text = " 1
+69  2  57
+    692
+  9   4
+47     2
+581 9   3
+  5  86
+ 4 2  8 1
+   6   4";
+        let board = Sudoku::from_text(text).unwrap();
+        assert_eq!(count_solutions(board, 2), 1);
+        let ambiguous = Sudoku::from_text("123456789").unwrap();
+        assert_eq!(count_solutions(ambiguous, 5), 5);
+        let unsolvable = Sudoku::from_text(&text.replacen(' ', "2", 1)).unwrap();
+        assert_eq!(count_solutions(unsolvable, 2), 0);
+    }
+
+    #[test]
+    fn solve_sudoku_bounded_works() {
+        let text = "1    7 9
+ 3  2   8
+  96  5
+  53  9
+ 1  8   2
+6    4
+3      1
+ 4      7
+  7   3";
+        let board = Sudoku::from_text(text).unwrap();
+        let (_, time) = solve_sudoku(board.clone()).unwrap();
+        let (err, time_bounded) = solve_sudoku_bounded(board.clone(), 2).unwrap_err();
+        assert_eq!(err, SolveError::BudgetExceeded);
+        assert_eq!(time_bounded, 2);
+        let (solution, _) = solve_sudoku_bounded(board.clone(), time).unwrap();
+        assert_eq!(Ok(solution), board.solved());
+
+        // The logic pass is charged too, even when it solves the board
+        let easy = "  3 2 6
+9  3 5  1
+  18 64
+  81 29
+7       8
+  67 82
+  26 95
+8  2 3  9
+  5 1 3";
+        let easy = Sudoku::from_text(easy).unwrap();
+        let err = solve_sudoku_bounded(easy.clone(), 0).unwrap_err();
+        assert_eq!(err, (SolveError::BudgetExceeded, 0));
+        let (solution, time) = solve_sudoku_bounded(easy.clone(), 1).unwrap();
+        assert_eq!(time, 1);
+        assert_eq!(Ok(solution), easy.solved());
+    }
+
+    #[test]
+    fn solve_logic_only_works() {
+        let text = " 1
+69  2  57
+    692
+  9   4
+47     2
+581 9   3
+  5  86
+ 4 2  8 1
+   6   4";
+        let board = Sudoku::from_text(text).unwrap();
+        let solution = solve_logic_only(board.clone());
+        assert!(solution.is_solved());
+        assert_eq!(Ok(solution), board.solved());
+        let text = "1    7 9
+ 3  2   8
+  96  5
+  53  9
+ 1  8   2
+6    4
+3      1
+ 4      7
+  7   3";
+        let board = Sudoku::from_text(text).unwrap();
+        assert!(!solve_logic_only(board).is_solved());
+    }
+
+    #[test]
+    fn iteration_difficulty_works() {
+        let easy = Sudoku::from_text(
+            "  3 2 6
+9  3 5  1
+  18 64
+  81 29
+7       8
+  67 82
+  26 95
+8  2 3  9
+  5 1 3",
+        )
+        .unwrap();
+        let hard = Sudoku::from_text(
+            "1    7 9
+ 3  2   8
+  96  5
+  53  9
+ 1  8   2
+6    4
+3      1
+ 4      7
+  7   3",
+        )
+        .unwrap();
+        let easy_score = iteration_difficulty(&easy);
+        let hard_score = iteration_difficulty(&hard);
+        println!("easy: {easy_score}, hard: {hard_score}");
+        assert_eq!(easy_score, 0.0);
+        assert!(hard_score > easy_score);
+        assert!(hard_score <= 100.0);
+    }
+
+    #[test]
+    fn rate_difficulty_works() {
+        let cases = [
+            (
+                "  3 2 6
+9  3 5  1
+  18 64
+  81 29
+7       8
+  67 82
+  26 95
+8  2 3  9
+  5 1 3",
+                Difficulty::Easy,
+            ),
+            (
+                "      39
+6       7
+ 571
+     74 5
+  3 1
+58  927
+13
+ 46  5 3
+  8",
+                Difficulty::Medium,
+            ),
+            (
+                "       9
+   8  1
+  7   2
+   3 7
+   51
+58149 76
+  5     2
+9 6    3
+ 2  3 54",
+                Difficulty::Hard,
+            ),
+            (
+                " 1
+69  2  57
+    692
+  9   4
+47     2
+581 9   3
+  5  86
+ 4 2  8 1
+   6   4",
+                Difficulty::Hard,
+            ),
+            (
+                "52941 7 3
+  6  3  2
+  32
+ 523   76
+637 5 2
+19 62753
+3   6942
+2  83 6
+96 7423 5",
+                Difficulty::Expert,
+            ),
+            (
+                "1    7 9
+ 3  2   8
+  96  5
+  53  9
+ 1  8   2
+6    4
+3      1
+ 4      7
+  7   3",
+                Difficulty::Extreme,
+            ),
+        ];
+        for (text, expected) in cases {
+            let board = Sudoku::from_text(text).unwrap();
+            assert_eq!(rate_difficulty(&board), expected);
+        }
+    }
+
+    #[test]
+    fn solve_sudoku_parallel_round_robin_works() {
+        let text = "1    7 9
+ 3  2   8
+  96  5
+  53  9
+ 1  8   2
+6    4
+3      1
+ 4      7
+  7   3";
+        let board = Sudoku::from_text(text).unwrap();
+        let (expected_solution, _) = solve_sudoku(board.clone()).unwrap();
+        let runs = (0..3)
+            .map(|_| solve_sudoku_parallel_round_robin(board.clone()).unwrap())
+            .collect::<Vec<_>>();
+        for (solution, time) in &runs {
+            println!("Round robin time: {time}");
+            assert_eq!(*solution, expected_solution);
+            assert_eq!(*time, runs[0].1);
+        }
+    }
+
+    #[test]
+    fn solve_sudoku_with_order_works() {
+        let text = "1    7 9
+ 3  2   8
+  96  5
+  53  9
+ 1  8   2
+6    4
+3      1
+ 4      7
+  7   3";
+        let board = Sudoku::from_text(text).unwrap();
+        // (0, 0) is a clue, so the first branch must happen at (8, 8)
+        let order = [(0, 0), (8, 8), (4, 0)];
+        let graph = OrderedSolver {
+            order: order.to_vec(),
+        };
+        let mut root = board.clone();
+        graph.check_goal(&mut root);
+        assert_eq!(root.state, SudokuState::HaveGuesses((8, 8)));
+        assert!(graph
+            .neighbours(&root)
+            .iter()
+            .all(|child| matches!(child.get(8, 8), Item::Number(_))));
+
+        let (expected_solution, _) = solve_sudoku(board.clone()).unwrap();
+        let (solution, time) = solve_sudoku_with_order(board, &order).unwrap();
+        println!("Time: {time}");
+        assert_eq!(solution, expected_solution);
+    }
+
+    #[test]
+    fn solutions_works() {
+        let board = Sudoku::from_text("").unwrap();
+        let found = solutions(board).take(2).collect::<Vec<_>>();
+        assert_eq!(found.len(), 2);
+        assert!(found.iter().all(|solution| solution.is_solved()));
+        assert_ne!(found[0], found[1]);
+
+        let text = "1    7 9
+ 3  2   8
+  96  5
+  53  9
+ 1  8   2
+6    4
+3      1
+ 4      7
+  7   3";
+        let board = Sudoku::from_text(text).unwrap();
+        let (expected_solution, _) = solve_sudoku(board.clone()).unwrap();
+        let found = solutions(board).collect::<Vec<_>>();
+        assert_eq!(found, vec![expected_solution]);
+    }
+
+    #[test]
+    fn is_unique_parallel_works() {
+        let texts = [
+            "1    7 9
+ 3  2   8
+  96  5
+  53  9
+ 1  8   2
+6    4
+3      1
+ 4      7
+  7   3",
+            "      39
+6       7
+ 571
+     74 5
+  3 1
+58  927
+13
+ 46  5 3
+  8",
+            "",
+            "  3
+456
+789",
+        ];
+        for text in texts {
+            let board = Sudoku::from_text(text).unwrap();
+            let expected = count_solutions(board.clone(), 2) == 1;
+            assert_eq!(is_unique_parallel(&board), expected);
+        }
+        let solved = Sudoku::from_text(texts[0]).unwrap().solved().unwrap();
+        assert!(is_unique_parallel(&solved));
+    }
+
+    #[test]
+    fn first_guess_point_works() {
+        let text = "  3 2 6
+9  3 5  1
+  18 64
+  81 29
+7       8
+  67 82
+  26 95
+8  2 3  9
+  5 1 3";
+        assert_eq!(first_guess_point(Sudoku::from_text(text).unwrap()), None);
+
+        let text = "1    7 9
+ 3  2   8
+  96  5
+  53  9
+ 1  8   2
+6    4
+3      1
+ 4      7
+  7   3";
+        let board = Sudoku::from_text(text).unwrap();
+        let (i, j) = first_guess_point(board.clone()).unwrap();
+        println!("First guess at ({i},{j})");
+        let stalled = solve_logic_only(board);
+        assert!(matches!(stalled.get(i, j), Item::Guesses(guesses) if guesses.len() > 1));
+    }
+
+    #[test]
+    fn solve_limited_guesses_works() {
+        let easy = "  3 2 6
+9  3 5  1
+  18 64
+  81 29
+7       8
+  67 82
+  26 95
+8  2 3  9
+  5 1 3";
+        let hard = "1    7 9
+ 3  2   8
+  96  5
+  53  9
+ 1  8   2
+6    4
+3      1
+ 4      7
+  7   3";
+        let easy = Sudoku::from_text(easy).unwrap();
+        let hard = Sudoku::from_text(hard).unwrap();
+        let (solution, _) = solve_limited_guesses(easy, 0).unwrap();
+        assert!(solution.is_solved());
+        let (err, _) = solve_limited_guesses(hard.clone(), 0).unwrap_err();
+        assert_eq!(err, SolveError::TooManyGuesses);
+        let (solution, time) = solve_limited_guesses(hard.clone(), 81).unwrap();
+        println!("Time: {time}");
+        assert_eq!(solution, hard.solved().unwrap());
+
+        let unsolvable = Sudoku::from_text(" 23456789\n\n\n\n1").unwrap();
+        let (err, _) = solve_limited_guesses(unsolvable, 81).unwrap_err();
+        assert_eq!(err, SolveError::NoSolution);
+        // Logic stalls on this one, so the limit cuts the search before it runs out
+        let mut unsolvable = hard;
+        unsolvable.set(0, 1, 2);
+        assert!(first_guess_point(unsolvable.clone()).is_some());
+        for max_guesses in [0, 1] {
+            let (err, _) = solve_limited_guesses(unsolvable.clone(), max_guesses).unwrap_err();
+            assert_eq!(err, SolveError::NoSolution);
+        }
+    }
+
+    #[test]
+    fn solve_sudoku_verbose_works() {
+        let text = " 1
+69  2  57
+    692
+  9   4
+47     2
+581 9   3
+  5  86
+ 4 2  8 1
+   6   4";
+        let board = Sudoku::from_text(text).unwrap();
+        let (solution, last_branch) = solve_sudoku_verbose(board.clone()).unwrap();
+        println!("{last_branch}");
+        assert!(solution.is_solved());
+        assert_eq!(solution.filled_count(), 81);
+        assert!(solution.solution_eq(&solve_sudoku(board).unwrap().0));
+        let (i, j) = match last_branch.state {
+            SudokuState::HaveGuesses(cell) => cell,
+            _ => unreachable!(),
+        };
+        assert!(matches!(last_branch.get(i, j), Item::Guesses(guesses) if guesses.len() > 1));
+        assert!(last_branch.filled_count() < 81);
+    }
+
+    #[test]
+    fn cross_check_works() {
+        let texts = [
+            "  3 2 6
+9  3 5  1
+  18 64
+  81 29
+7       8
+  67 82
+  26 95
+8  2 3  9
+  5 1 3",
+            " 1
+69  2  57
+    692
+  9   4
+47     2
+581 9   3
+  5  86
+ 4 2  8 1
+   6   4",
+            "1    7 9
+ 3  2   8
+  96  5
+  53  9
+ 1  8   2
+6    4
+3      1
+ 4      7
+  7   3",
+            "      39
+6       7
+ 571
+     74 5
+  3 1
+58  927
+13
+ 46  5 3
+  8",
+            "       9
+   8  1
+  7   2
+   3 7
+   51
+58149 76
+  5     2
+9 6    3
+ 2  3 54",
+        ];
+        for text in texts {
+            let board = Sudoku::from_text(text).unwrap();
+            let solution = cross_check(board.clone()).unwrap();
+            assert!(solution.is_solved());
+        }
+        let unsolvable = Sudoku::from_text(" 23456789\n\n\n\n1").unwrap();
+        let err = cross_check(unsolvable).unwrap_err();
+        println!("{err}");
+        assert!(err.starts_with("Neither"));
+    }
+
+    #[test]
+    fn solve_sudoku_fast_path() {
+        let easy = "  3 2 6
+9  3 5  1
+  18 64
+  81 29
+7       8
+  67 82
+  26 95
+8  2 3  9
+  5 1 3";
+        let (solution, time) = solve_sudoku(Sudoku::from_text(easy).unwrap()).unwrap();
+        assert_eq!(time, 1);
+        assert!(solution.is_solved());
+
+        let hard = "1    7 9
+ 3  2   8
+  96  5
+  53  9
+ 1  8   2
+6    4
+3      1
+ 4      7
+  7   3";
+        let board = Sudoku::from_text(hard).unwrap();
+        let (solution, time) = solve_sudoku(board.clone()).unwrap();
+        println!("Time: {time}");
+        assert!(time > 1);
+        let mut cells: [u8; 81] = board.to_flat().try_into().unwrap();
+        assert!(solve_flat(&mut cells));
+        assert_eq!(solution.to_flat(), cells);
+    }
+
+    #[test]
+    fn solve_until_works() {
+        let board = Sudoku::from_text("").unwrap();
+        let row_complete =
+            |board: &Sudoku| (0..9).all(|j| matches!(board.get(0, j), Item::Number(_)));
+        let (partial, time) = solve_until(board, row_complete).unwrap();
+        println!("Time: {time}\n{partial}");
+        assert!(row_complete(&partial));
+        assert!(partial.filled_count() < 81);
+        assert!(partial.snapshot().solved().is_ok());
+
+        let unsolvable = Sudoku::from_text(" 23456789\n\n\n\n1").unwrap();
+        let (err, _) = solve_until(unsolvable, row_complete).unwrap_err();
+        assert_eq!(err, SolveError::NoSolution);
+    }
+
+    #[test]
+    fn stats_to_json_works() {
+        let text = "1    7 9
+ 3  2   8
+  96  5
+  53  9
+ 1  8   2
+6    4
+3      1
+ 4      7
+  7   3";
+        let board = Sudoku::from_text(text).unwrap();
+        let (solution, stats) = solve_sudoku_stats(board.clone(), false);
+        assert!(solution.unwrap().is_solved());
+        assert_eq!(stats.depth_profile, None);
+        println!("{stats:?}");
+        assert!(stats.max_depth > 0 && stats.max_depth < 81);
+        assert!(stats.branching_factor > 1.0);
+        assert!(stats.pruned > 0);
+
+        let json = stats_to_json(&stats);
+        println!("{json}");
+        let fields = json
+            .trim_start_matches('{')
+            .trim_end_matches('}')
+            .split(',')
+            .map(|field| {
+                let (key, value) = field.split_once(':').unwrap();
+                (key.trim_matches('"'), value.parse::<f64>().unwrap())
+            })
+            .collect::<std::collections::HashMap<_, _>>();
+        assert_eq!(fields.len(), 5);
+        assert_eq!(fields["iterations"], stats.iterations as f64);
+        assert_eq!(fields["max_depth"], stats.max_depth as f64);
+        assert_eq!(fields["pruned"], stats.pruned as f64);
+        assert_eq!(fields["branching_factor"], stats.branching_factor);
+        assert!(fields["duration_ms"] >= 0.0);
+    }
+
+    #[test]
+    fn render_search_profile_works() {
+        let text = "1    7 9
+ 3  2   8
+  96  5
+  53  9
+ 1  8   2
+6    4
+3      1
+ 4      7
+  7   3";
+        let board = Sudoku::from_text(text).unwrap();
+        let (_, stats) = solve_sudoku_stats(board, true);
+        let samples = stats.depth_profile.unwrap();
+        assert_eq!(samples.len(), stats.iterations);
+        assert_eq!(samples.iter().max(), Some(&stats.max_depth));
+        let profile = render_search_profile(&samples);
+        println!("{profile}");
+        assert_eq!(profile.chars().count(), samples.len());
+        assert!(profile.starts_with(' ') && profile.contains('@'));
+        assert_eq!(render_search_profile(&[0, 1, 2, 4]), " :=@");
+        assert_eq!(render_search_profile(&[]), "");
+    }
+
+    #[test]
+    fn solve_sudoku_recursive_works() {
+        let text = "1    7 9
+ 3  2   8
+  96  5
+  53  9
+ 1  8   2
+6    4
+3      1
+ 4      7
+  7   3";
+        let board = Sudoku::from_text(text).unwrap();
+        let (err, _) = solve_sudoku_recursive(board.clone(), 2).unwrap_err();
+        assert_eq!(err, SolveError::DepthExceeded);
+        let (solution, time) = solve_sudoku_recursive(board.clone(), 81).unwrap();
+        println!("Time: {time}");
+        assert!(solution.solution_eq(&solve_sudoku(board).unwrap().0));
+
+        let unsolvable = Sudoku::from_text(" 23456789\n\n\n\n1").unwrap();
+        let (err, _) = solve_sudoku_recursive(unsolvable, 81).unwrap_err();
+        assert_eq!(err, SolveError::NoSolution);
+    }
 }
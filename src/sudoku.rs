@@ -1,16 +1,212 @@
+use crate::{
+    prelude::*,
+    rng::Rng,
+    solver::{count_solutions, solve_sudoku, SolveError},
+};
+#[cfg(feature = "std")]
 use colored::Colorize;
+use core::cmp::Ordering;
 use itertools::Itertools;
+#[cfg(feature = "std")]
 use std::collections::HashSet;
 
+mod builder;
+mod formats;
+mod techniques;
+
+pub use builder::SudokuBuilder;
+pub use techniques::{ConjugatePair, Elimination, Technique, XWing};
+
+/// Size of the standard board, the default of `Sudoku`
 const N: usize = 9;
+/// Box geometry of the standard board
+const BOX: BoxShape = BoxShape {
+    width: 3,
+    height: 3,
+};
+/// Fewest clues a board with a unique solution can have
+const MIN_UNIQUE_CLUES: usize = 17;
+/// Largest board size, the candidates of a cell are kept in a `u16` mask
+const MAX_SIZE: usize = 16;
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
-pub struct Sudoku {
+/// Value of a cell char on a board with `size` values: digits, then letters in either case
+/// for values above 9 (`A` or `a` is 10, up to `G` for 16)
+fn char_to_digit(c: char, size: usize) -> Option<u16> {
+    let x = match c {
+        '1'..='9' => c.to_digit(10)?,
+        'a'..='z' | 'A'..='Z' => c.to_ascii_lowercase() as u32 - 'a' as u32 + 10,
+        _ => return None,
+    };
+    (x as usize <= size).then_some(x as u16)
+}
+
+/// Bit of digit `x` in a candidate mask, digits 1 to 16 fit in a `u16`
+const fn digit_bit(x: u16) -> u16 {
+    1 << (x - 1)
+}
+
+/// Digits set in a candidate mask, in increasing order
+fn mask_digits(mask: u16) -> impl Iterator<Item = u16> {
+    (1..=16).filter(move |&x| mask & digit_bit(x) != 0)
+}
+
+/// Drops the repeated items keeping the first of each, like `Itertools::unique` but without
+/// the hash set that needs std
+fn unique<T: PartialEq>(items: impl IntoIterator<Item = T>) -> Vec<T> {
+    let mut kept = Vec::new();
+    for item in items {
+        if !kept.contains(&item) {
+            kept.push(item);
+        }
+    }
+    kept
+}
+
+/// How `write_grid` draws a cell without a number: empty cells in a blue background, cells
+/// with guesses as a green `G` and errors in a red background
+#[cfg(feature = "std")]
+fn cell_marker(item: &Item) -> String {
+    match item {
+        Item::Guesses(_) => "G".green().to_string(),
+        Item::Error => " ".on_red().to_string(),
+        _ => " ".on_blue().to_string(),
+    }
+}
+
+/// How `write_grid` draws a cell without a number. There are no colors without std, so
+/// like `to_unicode_string` empty cells are blank, guesses are `G` and errors `X`.
+#[cfg(not(feature = "std"))]
+fn cell_marker(item: &Item) -> String {
+    match item {
+        Item::Guesses(_) => "G".to_string(),
+        Item::Error => "X".to_string(),
+        _ => " ".to_string(),
+    }
+}
+
+/// Inverse of `char_to_digit`, letters are uppercase
+fn digit_to_char(x: u16) -> char {
+    match x {
+        0..=9 => char::from(b'0' + x as u8),
+        x => char::from(b'A' + (x - 10) as u8),
+    }
+}
+
+/// Size of the boxes in cells. Boards are `width * height` cells wide, so the geometry
+/// doesn't need to be square (like 6x6 boards with 2 rows by 3 columns boxes).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BoxShape {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl BoxShape {
+    fn size(self) -> usize {
+        self.width * self.height
+    }
+
+    /// Cells of the box with the given index, boxes are numbered left to right, top to bottom
+    fn cells(self, index: usize) -> Vec<(usize, usize)> {
+        let boxes_per_row = self.size() / self.width;
+        let i0 = (index / boxes_per_row) * self.height;
+        let j0 = (index % boxes_per_row) * self.width;
+        (i0..i0 + self.height)
+            .cartesian_product(j0..j0 + self.width)
+            .collect()
+    }
+
+    /// Index of the box containing (i, j)
+    fn index_of(self, i: usize, j: usize) -> usize {
+        let boxes_per_row = self.size() / self.width;
+        (i / self.height) * boxes_per_row + j / self.width
+    }
+}
+
+/// Board of `N` by `N` cells, 9 by default. Other sizes are built with
+/// `Sudoku::from_text_with_shape`.
+#[derive(Debug, Eq, PartialEq, Hash)]
+pub struct Sudoku<const N: usize = 9> {
     rows: [[Item; N]; N],
+    /// Box geometry, `N` cells in each box
+    shape: BoxShape,
+    /// Bitmask per cell of candidates the user marked as tried-and-failed
+    excluded: [[u16; N]; N],
+    /// Bitmask per cell of candidates removed by logic techniques
+    eliminated: [[u16; N]; N],
+    /// Cells that `try_set` and `clear` must not modify
+    locked: [[bool; N]; N],
+    /// Anti-knight variant: equal numbers can't be a knight's move apart
+    anti_knight: bool,
     pub state: SudokuState,
+    observer: Observer,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+impl<const N: usize> Clone for Sudoku<N> {
+    fn clone(&self) -> Self {
+        Self {
+            rows: self.rows.clone(),
+            shape: self.shape,
+            excluded: self.excluded,
+            eliminated: self.eliminated,
+            locked: self.locked,
+            anti_knight: self.anti_knight,
+            state: self.state.clone(),
+            observer: self.observer.clone(),
+        }
+    }
+
+    /// Copies `source` cell by cell, so the candidates of `self` keep their allocations (see
+    /// `Item::clone_from`). The solver builds its branches over pooled boards this way.
+    fn clone_from(&mut self, source: &Self) {
+        self.rows.clone_from(&source.rows);
+        self.shape = source.shape;
+        self.excluded = source.excluded;
+        self.eliminated = source.eliminated;
+        self.locked = source.locked;
+        self.anti_knight = source.anti_knight;
+        self.state = source.state.clone();
+        self.observer = source.observer.clone();
+    }
+}
+
+/// Callback for cell changes, see [`Sudoku::set_observer`]. It has to be `Send + Sync` because
+/// boards are `Send + Sync`: the parallel solvers hand them to other threads. State shared
+/// with a UI goes in an `Arc<Mutex<_>>` rather than an `Rc<RefCell<_>>`.
+pub type ObserverFn = Box<dyn Fn(usize, usize, &Item) + Send + Sync>;
+
+/// Optional observer, it is not part of the board: comparing and hashing ignore it and
+/// clones start without one (so the solver's copies don't notify)
+#[derive(Default)]
+struct Observer(Option<ObserverFn>);
+
+impl Clone for Observer {
+    fn clone(&self) -> Self {
+        Self(None)
+    }
+}
+
+impl PartialEq for Observer {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for Observer {}
+
+impl core::hash::Hash for Observer {
+    fn hash<H: core::hash::Hasher>(&self, _state: &mut H) {}
+}
+
+impl core::fmt::Debug for Observer {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.0 {
+            Some(_) => write!(f, "Observer(Some(..))"),
+            None => write!(f, "Observer(None)"),
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Hash)]
 pub enum Item {
     Number(u16),
     Empty,
@@ -18,6 +214,25 @@ pub enum Item {
     Error,
 }
 
+impl Clone for Item {
+    fn clone(&self) -> Self {
+        match self {
+            Item::Number(x) => Item::Number(*x),
+            Item::Empty => Item::Empty,
+            Item::Guesses(guesses) => Item::Guesses(guesses.clone()),
+            Item::Error => Item::Error,
+        }
+    }
+
+    /// Guesses copied over guesses reuse their allocation
+    fn clone_from(&mut self, source: &Self) {
+        match (self, source) {
+            (Item::Guesses(guesses), Item::Guesses(other)) => guesses.clone_from(other),
+            (item, source) => *item = source.clone(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum SudokuState {
     Unknown,
@@ -27,6 +242,100 @@ pub enum SudokuState {
     Solved,
 }
 
+/// Rows, columns and boxes, the groups of cells that must hold every digit once
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnitKind {
+    Row,
+    Column,
+    Box,
+}
+
+/// The 8 symmetries of the square, as transformations of the cell coordinates of the
+/// standard 9x9 board
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Symmetry {
+    Identity,
+    /// Clockwise rotations
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    /// Mirror of the columns, left to right
+    FlipHorizontal,
+    /// Mirror of the rows, top to bottom
+    FlipVertical,
+    /// Transpose over the main diagonal
+    FlipDiagonal,
+    FlipAntiDiagonal,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::FlipHorizontal,
+        Symmetry::FlipVertical,
+        Symmetry::FlipDiagonal,
+        Symmetry::FlipAntiDiagonal,
+    ];
+
+    /// Where the cell (i, j) of a 9x9 board ends up after applying the symmetry
+    pub fn apply(self, i: usize, j: usize) -> (usize, usize) {
+        let last = N - 1;
+        match self {
+            Symmetry::Identity => (i, j),
+            Symmetry::Rotate90 => (j, last - i),
+            Symmetry::Rotate180 => (last - i, last - j),
+            Symmetry::Rotate270 => (last - j, i),
+            Symmetry::FlipHorizontal => (i, last - j),
+            Symmetry::FlipVertical => (last - i, j),
+            Symmetry::FlipDiagonal => (j, i),
+            Symmetry::FlipAntiDiagonal => (last - j, last - i),
+        }
+    }
+}
+
+/// Reasons a board is not a proper puzzle, see `Sudoku::validate_puzzle`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PuzzleError {
+    /// A row, column or box repeats a number
+    InvalidConstraints,
+    NoSolution,
+    MultipleSolutions,
+    /// Less clues than any uniquely solvable board has
+    TooFewClues,
+}
+
+impl core::fmt::Display for PuzzleError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PuzzleError::InvalidConstraints => write!(f, "A row, column or box repeats a number"),
+            PuzzleError::NoSolution => write!(f, "The puzzle has no solution"),
+            PuzzleError::MultipleSolutions => write!(f, "The puzzle has more than one solution"),
+            PuzzleError::TooFewClues => {
+                write!(f, "The puzzle has less than {MIN_UNIQUE_CLUES} clues")
+            }
+        }
+    }
+}
+
+impl core::error::Error for PuzzleError {}
+
+impl UnitKind {
+    const ALL: [UnitKind; 3] = [UnitKind::Row, UnitKind::Column, UnitKind::Box];
+
+    /// Cells of the unit with the given index on the standard 9x9 board, boxes are numbered
+    /// left to right, top to bottom. Other sizes and box shapes need `Sudoku::unit_cells`.
+    pub fn cells(self, index: usize) -> Vec<(usize, usize)> {
+        match self {
+            UnitKind::Row => (0..N).map(|j| (index, j)).collect(),
+            UnitKind::Column => (0..N).map(|i| (i, index)).collect(),
+            UnitKind::Box => BOX.cells(index),
+        }
+    }
+}
+
 impl Item {
     fn get_number(&self) -> Option<u16> {
         if let Item::Number(x) = self {
@@ -39,7 +348,67 @@ impl Item {
 
 impl Sudoku {
     pub fn from_text(text: &str) -> Result<Self, String> {
-        let mut rows: [[Item; N]; N] = (0..N)
+        Self::from_text_with_shape(text, BOX)
+    }
+}
+
+impl<const N: usize> Sudoku<N> {
+    /// Parses a board of any size up to 16 with the given box geometry, which has to hold `N`
+    /// cells. Values above 9 are letters in either case (`A` or `a` is 10), spaces are empty
+    /// cells, and so is `0` like in the sudokuwiki format.
+    ///
+    /// ```
+    /// # use sudoku_solver::{BoxShape, Sudoku};
+    /// let shape = BoxShape { width: 3, height: 2 };
+    /// let board = Sudoku::<6>::from_text_with_shape("12\n  3", shape).unwrap();
+    /// assert_eq!(board.get_guesses(1, 0), vec![4, 5, 6]);
+    /// ```
+    pub fn from_text_with_shape(text: &str, shape: BoxShape) -> Result<Self, String> {
+        Self::check_size()?;
+        if shape.size() != N {
+            return Err(format!(
+                "Boxes of {}x{} don't fit a board of size {N}",
+                shape.width, shape.height
+            ));
+        }
+        let mut rows = Self::empty_rows();
+        for (i, line) in text.lines().enumerate() {
+            if i >= N {
+                return Err(format!("Too many lines, expected at most {N}"));
+            }
+            for (j, c) in line.chars().enumerate() {
+                if j >= N {
+                    return Err(format!("Line {i} is too long, expected at most {N} chars"));
+                }
+                rows[i][j] = match (c, char_to_digit(c, N)) {
+                    (' ' | '0', _) => Item::Empty,
+                    (_, Some(x)) => Item::Number(x),
+                    (c, None) => return Err(format!("Invalid char: {c}")),
+                };
+            }
+        }
+        // Point out where a box repeats a number, the most common typo in hand written grids
+        for b in 0..N {
+            let mut seen = [None; N];
+            for (i, j) in shape.cells(b) {
+                let Some(x) = rows[i][j].get_number() else {
+                    continue;
+                };
+                match seen[x as usize - 1] {
+                    Some((i0, j0)) => {
+                        return Err(format!(
+                            "Duplicate {x} in box {b} at ({i0},{j0}) and ({i},{j})"
+                        ))
+                    }
+                    None => seen[x as usize - 1] = Some((i, j)),
+                }
+            }
+        }
+        Self::from_rows(rows, shape)
+    }
+
+    fn empty_rows() -> [[Item; N]; N] {
+        (0..N)
             .map(|_| {
                 (0..N)
                     .map(|_| Item::Empty)
@@ -49,45 +418,311 @@ impl Sudoku {
             })
             .collect_vec()
             .try_into()
-            .unwrap();
-        for (i, line) in text.lines().enumerate() {
-            for (j, c) in line.chars().enumerate() {
-                match c {
-                    ' ' => {
-                        rows[i][j] = Item::Empty;
-                    }
-                    c => {
-                        let x = c
-                            .to_digit(10)
-                            .unwrap_or_else(|| panic!("Invalid char: {c}"))
-                            as u16;
-                        rows[i][j] = Item::Number(x);
-                    }
-                }
-            }
+            .unwrap()
+    }
+
+    /// Fails unless the board size is between 1 and `MAX_SIZE`
+    fn check_size() -> Result<(), String> {
+        match (1..=MAX_SIZE).contains(&N) {
+            true => Ok(()),
+            false => Err(format!(
+                "Boards of size {N} are not supported, the size goes from 1 to {MAX_SIZE}"
+            )),
         }
-        let board = Self {
+    }
+
+    fn from_rows(rows: [[Item; N]; N], shape: BoxShape) -> Result<Self, String> {
+        Self::check_size()?;
+        let mut board = Self {
             rows,
+            shape,
+            excluded: [[0; N]; N],
+            eliminated: [[0; N]; N],
+            locked: [[false; N]; N],
+            anti_knight: false,
             state: SudokuState::Unknown,
+            observer: Observer::default(),
         };
 
         if board.is_valid() {
+            // A complete valid grid needs no search to know it is solved
+            if board.filled_count() == N * N {
+                board.state = SudokuState::Solved;
+            }
             Ok(board)
         } else {
             Err("Invalid board".to_string())
         }
     }
 
+    /// Copy keeping only the numbers and the variant rules: guesses and errors become empty
+    /// cells, excluded or eliminated candidates and locks are forgotten and the state is
+    /// `Unknown`
+    pub fn snapshot(&self) -> Self {
+        let mut rows = Self::empty_rows();
+        for (i, j) in (0..N).cartesian_product(0..N) {
+            if let Item::Number(x) = self.get(i, j) {
+                rows[i][j] = Item::Number(*x);
+            }
+        }
+        Self {
+            rows,
+            shape: self.shape,
+            excluded: [[0; N]; N],
+            eliminated: [[0; N]; N],
+            locked: [[false; N]; N],
+            anti_knight: self.anti_knight,
+            state: SudokuState::Unknown,
+            observer: Observer::default(),
+        }
+    }
+
+    /// Turns on the anti-knight rule: equal numbers can't be a knight's move apart. Candidates
+    /// and validity checks (so the solvers too) follow it. Fails if placed numbers break it.
+    pub fn with_anti_knight(mut self) -> Result<Self, String> {
+        self.anti_knight = true;
+        match self.is_valid() {
+            true => Ok(self),
+            false => Err("Invalid board".to_string()),
+        }
+    }
+
     pub fn is_solved(&self) -> bool {
         matches!(self.state, SudokuState::Solved)
     }
 
+    /// Solves a copy of the board, returning only the solution
+    ///
+    /// ```
+    /// # use sudoku_solver::Sudoku;
+    /// # fn main() -> Result<(), Box<dyn core::error::Error>> {
+    /// let board = Sudoku::from_text(
+    ///     " 1
+    /// 69  2  57
+    ///     692
+    ///   9   4
+    /// 47     2
+    /// 581 9   3
+    ///   5  86
+    ///  4 2  8 1
+    ///    6   4",
+    /// )?;
+    /// let solution = board.solved()?;
+    /// assert!(solution.is_solved());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn solved(&self) -> Result<Self, SolveError> {
+        solve_sudoku(self.clone())
+            .map(|(solution, _)| solution)
+            .map_err(|(err, _)| err)
+    }
+
+    /// Whether the board has a solution. The search stops at the first solved board and
+    /// doesn't return it, use `solved` to get the solution.
+    pub fn is_solvable(&self) -> bool {
+        count_solutions(self.clone(), 1) == 1
+    }
+
+    /// Number of cells holding a number
+    pub fn clue_count(&self) -> usize {
+        self.filled_count()
+    }
+
+    /// Fraction (0.0 to 1.0) of the cells holding a number in each row, column and box
+    pub fn unit_fill_fractions(&self) -> (Vec<f32>, Vec<f32>, Vec<f32>) {
+        let fraction = |values: Vec<u16>| values.len() as f32 / N as f32;
+        let rows = (0..N).map(|i| fraction(self.get_row_values(i))).collect();
+        let cols = (0..N).map(|j| fraction(self.get_col_values(j))).collect();
+        let boxes = (0..N)
+            .map(|b| {
+                let (i, j) = self.box_cells(b)[0];
+                fraction(self.get_square_values(i, j))
+            })
+            .collect();
+        (rows, cols, boxes)
+    }
+
+    /// Number of clues in each box, boxes numbered left to right, top to bottom
+    pub fn box_clue_counts(&self) -> [u8; N] {
+        let mut counts = [0; N];
+        for (b, count) in counts.iter_mut().enumerate() {
+            *count = self
+                .box_cells(b)
+                .into_iter()
+                .filter(|&(i, j)| matches!(self.get(i, j), Item::Number(_)))
+                .count() as u8;
+        }
+        counts
+    }
+
+    /// Whether both boards are complete and hold the same numbers, ignoring the state and
+    /// excluded candidates
+    pub fn solution_eq(&self, other: &Self) -> bool {
+        self.filled_count() == N * N && other.filled_count() == N * N && self.rows == other.rows
+    }
+
+    /// Number of cells where the boards don't hold the same number, a number and an empty
+    /// cell count as different
+    pub fn hamming(&self, other: &Self) -> usize {
+        (0..N)
+            .cartesian_product(0..N)
+            .filter(|&(i, j)| self.get(i, j).get_number() != other.get(i, j).get_number())
+            .count()
+    }
+
+    /// Whether every number of this board is in the same cell of `solution`
+    pub fn is_givens_subset_of(&self, solution: &Self) -> bool {
+        (0..N)
+            .cartesian_product(0..N)
+            .all(|(i, j)| match self.get(i, j) {
+                Item::Number(x) => solution.get(i, j).get_number() == Some(*x),
+                _ => true,
+            })
+    }
+
+    /// Whether the board has a unique solution that is lost by removing any of its clues
+    pub fn is_minimal(&self) -> bool {
+        if count_solutions(self.clone(), 2) != 1 {
+            return false;
+        }
+        self.removal_impact()
+            .into_iter()
+            .all(|(_, solutions)| solutions > 1)
+    }
+
+    /// For every clue, the number of solutions (up to 2) of the board without it. Clues with
+    /// an impact of 1 can be removed keeping the solution unique.
+    pub fn removal_impact(&self) -> Vec<((usize, usize), usize)> {
+        (0..N)
+            .cartesian_product(0..N)
+            .filter(|&(i, j)| self.get(i, j).get_number().is_some())
+            .map(|(i, j)| {
+                let mut board = self.clone();
+                board.rows[i][j] = Item::Empty;
+                ((i, j), count_solutions(board, 2))
+            })
+            .collect()
+    }
+
     pub fn get(&self, i: usize, j: usize) -> &Item {
         &self.rows[i][j]
     }
 
     pub fn set(&mut self, i: usize, j: usize, number: u16) {
-        self.rows[i][j] = Item::Number(number);
+        self.replace_item(i, j, Item::Number(number));
+    }
+
+    /// Empties the cell at (i, j), fails if the cell is out of the board or locked. Removing a
+    /// number forgets the candidates eliminated by logic techniques, they may depend on it.
+    pub fn clear(&mut self, i: usize, j: usize) -> Result<(), String> {
+        self.check_unlocked(i, j)?;
+        self.replace_item(i, j, Item::Empty);
+        Ok(())
+    }
+
+    /// Prevents `try_set` and `clear` from modifying the cell at (i, j), fails if the cell is
+    /// out of the board. `set` is unchecked and ignores locks.
+    pub fn lock(&mut self, i: usize, j: usize) -> Result<(), String> {
+        Self::check_cell(i, j)?;
+        self.locked[i][j] = true;
+        Ok(())
+    }
+
+    /// Locks every cell that currently holds a number
+    pub fn lock_givens(&mut self) {
+        for (i, j) in (0..N).cartesian_product(0..N) {
+            if self.get(i, j).get_number().is_some() {
+                self.locked[i][j] = true;
+            }
+        }
+    }
+
+    fn check_cell(i: usize, j: usize) -> Result<(), String> {
+        match i < N && j < N {
+            true => Ok(()),
+            false => Err(format!("Cell ({i},{j}) is out of the board")),
+        }
+    }
+
+    /// Fails if the cell at (i, j) is out of the board or locked
+    fn check_unlocked(&self, i: usize, j: usize) -> Result<(), String> {
+        Self::check_cell(i, j)?;
+        match self.locked[i][j] {
+            true => Err(format!("Cell ({i},{j}) is locked")),
+            false => Ok(()),
+        }
+    }
+
+    /// Calls `observer` with the coordinates and new item whenever `set` or `clear` change a
+    /// cell. Clones of the board don't keep the observer. See [`ObserverFn`] for why it has to
+    /// be `Send + Sync`.
+    pub fn set_observer(&mut self, observer: ObserverFn) {
+        self.observer = Observer(Some(observer));
+    }
+
+    fn replace_item(&mut self, i: usize, j: usize, item: Item) {
+        if self.rows[i][j] == item {
+            return;
+        }
+        // Eliminations may have been derived from the number being removed
+        if matches!(self.rows[i][j], Item::Number(_)) {
+            self.clear_eliminations();
+        }
+        self.rows[i][j] = item;
+        if let Some(observer) = &self.observer.0 {
+            observer(i, j, &self.rows[i][j]);
+        }
+    }
+
+    /// Like `set`, but only places `number` if it is a legal move
+    pub fn try_set(&mut self, i: usize, j: usize, number: u16) -> Result<(), String> {
+        self.check_unlocked(i, j)?;
+        if let Item::Number(x) = self.get(i, j) {
+            return Err(format!("Cell ({i},{j}) already contains {x}"));
+        }
+        if !self.get_guesses(i, j).contains(&number) {
+            return Err(format!("{number} can't be placed at ({i},{j})"));
+        }
+        self.set(i, j, number);
+        Ok(())
+    }
+
+    /// Applies the placements in order, stopping at the first illegal one.
+    /// Returns the index of the failing move and the reason, moves before it stay applied.
+    pub fn apply_moves(&mut self, moves: &[(usize, usize, u16)]) -> Result<(), (usize, String)> {
+        for (k, &(i, j, number)) in moves.iter().enumerate() {
+            self.try_set(i, j, number).map_err(|err| (k, err))?;
+        }
+        Ok(())
+    }
+
+    /// Cells a knight's move away from (i, j)
+    fn knight_cells(i: usize, j: usize) -> impl Iterator<Item = (usize, usize)> {
+        const MOVES: [(isize, isize); 8] = [
+            (-2, -1),
+            (-2, 1),
+            (-1, -2),
+            (-1, 2),
+            (1, -2),
+            (1, 2),
+            (2, -1),
+            (2, 1),
+        ];
+        MOVES.into_iter().filter_map(move |(di, dj)| {
+            let (i, j) = (i.checked_add_signed(di)?, j.checked_add_signed(dj)?);
+            (i < N && j < N).then_some((i, j))
+        })
+    }
+
+    /// Numbers a knight's move away from (i, j), empty unless the anti-knight rule is on
+    fn get_knight_values(&self, i: usize, j: usize) -> Vec<u16> {
+        if !self.anti_knight {
+            return Vec::new();
+        }
+        Self::knight_cells(i, j)
+            .filter_map(|(i, j)| self.get(i, j).get_number())
+            .collect()
     }
 
     fn get_row_values(&self, i: usize) -> Vec<u16> {
@@ -105,133 +740,687 @@ impl Sudoku {
             .collect_vec()
     }
 
+    /// Cells of the box with the given index, boxes are numbered left to right, top to bottom
+    fn box_cells(&self, index: usize) -> Vec<(usize, usize)> {
+        self.shape.cells(index)
+    }
+
+    /// Cells of the unit with the given index, like `UnitKind::cells` with this board's size
+    /// and boxes
+    fn unit_cells(&self, kind: UnitKind, index: usize) -> Vec<(usize, usize)> {
+        match kind {
+            UnitKind::Row => (0..N).map(|j| (index, j)).collect(),
+            UnitKind::Column => (0..N).map(|i| (i, index)).collect(),
+            UnitKind::Box => self.box_cells(index),
+        }
+    }
+
+    /// Cells of every row, column and box (in that order)
+    fn units(&self) -> Vec<Vec<(usize, usize)>> {
+        UnitKind::ALL
+            .into_iter()
+            .cartesian_product(0..N)
+            .map(|(kind, index)| self.unit_cells(kind, index))
+            .collect()
+    }
+
+    pub(crate) fn filled_count(&self) -> usize {
+        self.rows
+            .iter()
+            .flatten()
+            .filter(|x| matches!(x, Item::Number(_)))
+            .count()
+    }
+
     fn get_square_values(&self, i: usize, j: usize) -> Vec<u16> {
-        let i0 = (i / 3) * 3;
-        let j0 = (j / 3) * 3;
-        (i0..i0 + 3)
-            .cartesian_product(j0..j0 + 3)
+        self.box_cells(self.shape.index_of(i, j))
+            .into_iter()
             .map(|(i, j)| self.get(i, j))
             .filter_map(|x| x.get_number())
             .collect_vec()
     }
 
     pub fn get_guesses(&self, i: usize, j: usize) -> Vec<u16> {
-        let others = self
-            .get_row_values(i)
-            .into_iter()
-            .chain(self.get_col_values(j).into_iter())
-            .chain(self.get_square_values(i, j).into_iter())
-            .collect::<HashSet<_>>();
-        (1..=9).filter(|x| !others.contains(x)).collect()
+        mask_digits(self.candidate_mask(i, j)).collect()
     }
 
-    pub fn compute_guesses(&mut self) {
-        let guess_idxs = self
-            .rows
-            .iter()
-            .enumerate()
-            .map(|(i, row)| {
-                row.iter().enumerate().filter_map(move |(j, x)| {
-                    if let Item::Number(_) = x {
-                        None
-                    } else {
-                        Some((i, j))
-                    }
-                })
+    /// Candidates of (i, j) as a mask of `digit_bit`s: the digits no other cell of its row,
+    /// column or box (nor a knight's move away with the anti-knight rule) has and that
+    /// weren't excluded or eliminated
+    fn candidate_mask(&self, i: usize, j: usize) -> u16 {
+        // Walks the cells in place, the solver calls this for every cell of every node
+        let BoxShape { width, height } = self.shape;
+        let (i0, j0) = (i - i % height, j - j % width);
+        let square = (i0..i0 + height).cartesian_product(j0..j0 + width);
+        let knight = Self::knight_cells(i, j).filter(|_| self.anti_knight);
+        let taken = (0..N)
+            .map(|k| (i, k))
+            .chain((0..N).map(|k| (k, j)))
+            .chain(square)
+            .chain(knight)
+            .filter_map(|(i, j)| self.get(i, j).get_number())
+            .fold(self.excluded[i][j] | self.eliminated[i][j], |mask, x| {
+                mask | digit_bit(x)
+            });
+        !taken & (u16::MAX >> (16 - N))
+    }
+
+    /// Same candidates as `get_guesses`, in a random order drawn from `rng`
+    pub fn get_guesses_shuffled(&self, i: usize, j: usize, rng: &mut impl Rng) -> Vec<u16> {
+        let mut guesses = self.get_guesses(i, j);
+        rng.shuffle(&mut guesses);
+        guesses
+    }
+
+    /// Empty cells where `digit` can be placed
+    pub fn candidate_cells(&self, digit: u16) -> Vec<(usize, usize)> {
+        (0..N)
+            .cartesian_product(0..N)
+            .filter(|&(i, j)| {
+                self.get(i, j).get_number().is_none() && self.get_guesses(i, j).contains(&digit)
             })
-            .flatten()
-            .collect_vec();
+            .collect()
+    }
 
+    /// Marks `value` as tried-and-failed at (i, j), so it is no longer returned by `get_guesses`.
+    /// Fails if the cell is out of the board or the value is not a digit of the board.
+    pub fn exclude_candidate(&mut self, i: usize, j: usize, value: u16) -> Result<(), String> {
+        Self::check_cell(i, j)?;
+        if !(1..=N as u16).contains(&value) {
+            return Err(format!("Invalid value: {value}"));
+        }
+        self.excluded[i][j] |= digit_bit(value);
+        Ok(())
+    }
+
+    /// Removes `value` from the candidates of (i, j) as the result of a logic technique, kept
+    /// apart from the user's exclusions
+    fn eliminate_candidate(&mut self, i: usize, j: usize, value: u16) {
+        self.eliminated[i][j] |= digit_bit(value);
+    }
+
+    /// Forgets the candidates removed by logic techniques, the user's exclusions are kept
+    pub(crate) fn clear_eliminations(&mut self) {
+        self.eliminated = [[0; N]; N];
+    }
+
+    pub fn compute_guesses(&mut self) {
+        self.compute_guesses_tracked();
+    }
+
+    /// Same as `compute_guesses`, returns the cells whose `Item` changed
+    pub fn compute_guesses_tracked(&mut self) -> Vec<(usize, usize)> {
+        let mut changed = Vec::new();
+        let filled = self.update_guesses(&mut changed);
+        changed.extend(filled.into_iter().map(|(i, j, _)| (i, j)));
+        changed.sort_unstable();
+        changed
+    }
+
+    /// `compute_guesses`, returning the cells filled with their single candidate. The other
+    /// cells whose `Item` changed are added to `changed`.
+    fn update_guesses(&mut self, changed: &mut Vec<(usize, usize)>) -> Vec<(usize, usize, u16)> {
         let mut invalid = false;
-        let guess_idxs = guess_idxs
-            .into_iter()
-            .filter_map(|(i, j)| {
-                let guesses = self.get_guesses(i, j);
-                match guesses.len() {
-                    0 => {
-                        invalid = true;
-                        self.rows[i][j] = Item::Error;
-                        None
-                    }
-                    1 => {
-                        self.rows[i][j] = Item::Number(guesses[0]);
-                        None
-                    }
-                    _ => {
-                        self.rows[i][j] = Item::Guesses(guesses);
-                        Some((i, j))
-                    }
+        let mut next_guess = None;
+        let filled = self.fill_singles_pass(|board, (i, j), mask| {
+            if mask == 0 {
+                invalid = true;
+                if board.rows[i][j] != Item::Error {
+                    board.rows[i][j] = Item::Error;
+                    changed.push((i, j));
                 }
-            })
-            .collect_vec();
+                return;
+            }
+            next_guess.get_or_insert((i, j));
+            // Rewrites the candidates in place when they changed, keeping their allocation
+            match &mut board.rows[i][j] {
+                Item::Guesses(guesses) if mask_digits(mask).eq(guesses.iter().copied()) => {}
+                Item::Guesses(guesses) => {
+                    guesses.clear();
+                    guesses.extend(mask_digits(mask));
+                    changed.push((i, j));
+                }
+                item => {
+                    *item = Item::Guesses(mask_digits(mask).collect());
+                    changed.push((i, j));
+                }
+            }
+        });
         if invalid {
             self.state = SudokuState::Invalid;
-        } else if let Some(&idx) = guess_idxs.first() {
+        } else if let Some(idx) = next_guess {
             self.state = SudokuState::HaveGuesses(idx);
         } else {
             self.state = SudokuState::Solved;
         }
+        filled
     }
 
-    fn is_valid(&self) -> bool {
-        let groups = (0..N)
-            .map(|i| self.get_row_values(i))
-            .chain((0..N).map(|j| self.get_col_values(j)))
-            .chain(
-                (0..2)
-                    .cartesian_product(0..2)
-                    .map(|(i, j)| self.get_square_values(i, j)),
-            )
-            .map(|group| group.into_iter().counts().into_values().max().unwrap_or(0))
-            .max()
-            .unwrap_or(0);
-        groups <= 1
-    }
-}
-
-impl std::fmt::Display for Sudoku {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut line = String::new();
-        let horizontal_line = " ----------------- ";
-        for (i, row) in self.rows.iter().enumerate() {
-            if i % 3 == 0 {
-                writeln!(f, "{}", horizontal_line)?;
+    /// One row-major pass over the cells without a number placing the ones with a single
+    /// candidate, so later cells already see the earlier placements. `unfilled` gets the
+    /// candidate mask of every other cell. Returns the filled cells with their numbers.
+    fn fill_singles_pass(
+        &mut self,
+        mut unfilled: impl FnMut(&mut Self, (usize, usize), u16),
+    ) -> Vec<(usize, usize, u16)> {
+        let mut filled = Vec::new();
+        for (i, j) in (0..N).cartesian_product(0..N) {
+            if matches!(self.get(i, j), Item::Number(_)) {
+                continue;
             }
-            for (j, x) in row.iter().enumerate() {
-                line.push(if j % 3 == 0 { '|' } else { ' ' });
-                match x {
-                    Item::Number(n) => {
-                        line.push_str(&format!("{n}"));
-                    }
-                    Item::Empty => {
-                        line.push_str(&" ".on_blue().to_string());
-                    }
-                    Item::Guesses(_) => {
-                        line.push_str(&"G".green().to_string());
-                    }
-                    Item::Error => {
-                        line.push_str(&" ".on_red().to_string());
-                    }
-                }
+            let mask = self.candidate_mask(i, j);
+            if mask.count_ones() == 1 {
+                let x = mask.trailing_zeros() as u16 + 1;
+                self.set(i, j, x);
+                filled.push((i, j, x));
+            } else {
+                unfilled(self, (i, j), mask);
             }
-            writeln!(f, "{line}|")?;
-            line.clear();
         }
-        writeln!(f, "{}", horizontal_line)
+        filled
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Digits that are already placed `N` times
+    pub fn completed_digits(&self) -> Vec<u16> {
+        let mut counts = [0; N];
+        for x in self.rows.iter().flatten().filter_map(Item::get_number) {
+            counts[x as usize - 1] += 1;
+        }
+        (1..=N as u16)
+            .filter(|&x| counts[x as usize - 1] == N)
+            .collect()
+    }
 
-    #[test]
-    fn create_sudoku_from_text_works() {
-        let text = " 1
-69  2  57
-    692
-  9   4
-47     2
+    /// Number of branches the solver would create next, 0 if there is no pending guess
+    pub fn branch_count(&self) -> usize {
+        match self.state {
+            SudokuState::HaveGuesses((i, j)) => self.get_guesses(i, j).len(),
+            _ => 0,
+        }
+    }
+
+    /// Sum of `log2(candidates)` over the empty cells, a rough difficulty proxy
+    #[cfg(feature = "std")]
+    pub fn entropy(&self) -> f64 {
+        (0..N)
+            .cartesian_product(0..N)
+            .filter(|&(i, j)| self.get(i, j).get_number().is_none())
+            .map(|(i, j)| self.get_guesses(i, j).len())
+            .filter(|&count| count > 0)
+            .map(|count| (count as f64).log2())
+            .sum()
+    }
+
+    /// Placed cells that share a row, column or box with the same number
+    pub fn find_conflicts(&self) -> Vec<(usize, usize)> {
+        self.overfilled()
+            .into_iter()
+            .map(|(cell, _)| cell)
+            .collect()
+    }
+
+    /// Every placed cell involved in a repeated number of some row, column or box, same as
+    /// `find_conflicts` as a set
+    #[cfg(feature = "std")]
+    pub fn all_conflicts(&self) -> HashSet<(usize, usize)> {
+        self.find_conflicts().into_iter().collect()
+    }
+
+    /// Like `find_conflicts`, with the number that is repeated in the unit of each cell
+    pub fn overfilled(&self) -> Vec<((usize, usize), u16)> {
+        self.units()
+            .into_iter()
+            .flat_map(|unit| {
+                let placed = unit
+                    .iter()
+                    .filter_map(|&(i, j)| self.get(i, j).get_number().map(|x| ((i, j), x)))
+                    .collect_vec();
+                placed
+                    .iter()
+                    .filter(|&&(cell, x)| placed.iter().any(|&(other, y)| other != cell && y == x))
+                    .copied()
+                    .collect_vec()
+            })
+            .sorted()
+            .dedup()
+            .collect()
+    }
+
+    /// Units that already hold every digit
+    pub fn complete_units(&self) -> Vec<(UnitKind, usize)> {
+        UnitKind::ALL
+            .into_iter()
+            .cartesian_product(0..N)
+            .filter(|&(kind, index)| {
+                self.unit_cells(kind, index)
+                    .into_iter()
+                    .all(|(i, j)| matches!(self.get(i, j), Item::Number(_)))
+            })
+            .collect()
+    }
+
+    /// Empty or guess cells without any candidate left, each one proves the board has no
+    /// solution
+    pub fn dead_cells(&self) -> Vec<(usize, usize)> {
+        (0..N)
+            .cartesian_product(0..N)
+            .filter(|&(i, j)| {
+                self.get(i, j).get_number().is_none() && self.get_guesses(i, j).is_empty()
+            })
+            .collect()
+    }
+
+    /// A unit and a digit missing from it that can't go in any of its empty cells, which
+    /// proves the board has no solution. Returns the first one found, rows first.
+    pub fn find_unsolvable_unit(&self) -> Option<(UnitKind, usize, u16)> {
+        UnitKind::ALL
+            .into_iter()
+            .cartesian_product(0..N)
+            .find_map(|(kind, index)| {
+                let cells = self.unit_cells(kind, index);
+                let covered = cells.iter().fold(0, |mask, &(i, j)| match self.get(i, j) {
+                    Item::Number(x) => mask | digit_bit(*x),
+                    _ => mask | self.candidate_mask(i, j),
+                });
+                (1..=N as u16)
+                    .find(|&x| covered & digit_bit(x) == 0)
+                    .map(|x| (kind, index, x))
+            })
+    }
+
+    /// Renders the board like `Display`, with conflicting cells in a red background
+    #[cfg(feature = "std")]
+    pub fn format_with_conflicts(&self) -> String {
+        let mut s = String::new();
+        let conflicts = self.find_conflicts();
+        self.write_grid(&mut s, |cell, n| match conflicts.contains(&cell) {
+            true => digit_to_char(n).to_string().on_red().to_string(),
+            false => digit_to_char(n).to_string(),
+        })
+        .unwrap();
+        s
+    }
+
+    /// Renders the board like `Display`, with the numbers given in `original` in cyan and the
+    /// ones filled since (like by the solver) in yellow
+    #[cfg(feature = "std")]
+    pub fn to_overlay_string(&self, original: &Self) -> String {
+        let mut s = String::new();
+        self.write_grid(&mut s, |(i, j), n| {
+            let digit = digit_to_char(n).to_string();
+            match original.get(i, j) {
+                Item::Number(_) => digit.cyan().to_string(),
+                _ => digit.yellow().to_string(),
+            }
+        })
+        .unwrap();
+        s
+    }
+
+    /// Renders the board with Unicode box-drawing characters, thin lines between cells and
+    /// thick ones around the boxes. Unlike `Display` it has no colors: empty cells are blank,
+    /// cells with guesses are shown as `G` and errors as `X`.
+    pub fn to_unicode_string(&self) -> String {
+        let border = |left: char, thick: char, thin: char, right: char, fill: char| {
+            let mut line = String::from(left);
+            for j in 0..N {
+                line.push(fill);
+                line.push(match j + 1 {
+                    k if k == N => right,
+                    k if k % self.shape.width == 0 => thick,
+                    _ => thin,
+                });
+            }
+            line.push('\n');
+            line
+        };
+        let mut s = border('┏', '┳', '┯', '┓', '━');
+        for (i, row) in self.rows.iter().enumerate() {
+            s.push('┃');
+            for (j, x) in row.iter().enumerate() {
+                s.push(match x {
+                    Item::Number(n) => digit_to_char(*n),
+                    Item::Empty => ' ',
+                    Item::Guesses(_) => 'G',
+                    Item::Error => 'X',
+                });
+                s.push(if (j + 1) % self.shape.width == 0 {
+                    '┃'
+                } else {
+                    '│'
+                });
+            }
+            s.push('\n');
+            s.push_str(&match i + 1 {
+                k if k == N => border('┗', '┻', '┷', '┛', '━'),
+                k if k % self.shape.height == 0 => border('┣', '╋', '┿', '┫', '━'),
+                _ => border('┠', '╂', '┼', '┨', '─'),
+            });
+        }
+        s
+    }
+
+    /// Renders the board like `Display` with `number` styling the cells that hold a number
+    fn write_grid(
+        &self,
+        f: &mut impl core::fmt::Write,
+        number: impl Fn((usize, usize), u16) -> String,
+    ) -> core::fmt::Result {
+        let mut line = String::new();
+        let horizontal_line = format!(" {} ", "-".repeat(2 * N - 1));
+        for (i, row) in self.rows.iter().enumerate() {
+            if i % self.shape.height == 0 {
+                writeln!(f, "{}", horizontal_line)?;
+            }
+            for (j, x) in row.iter().enumerate() {
+                line.push(if j % self.shape.width == 0 { '|' } else { ' ' });
+                match x {
+                    Item::Number(n) => line.push_str(&number((i, j), *n)),
+                    x => line.push_str(&cell_marker(x)),
+                }
+            }
+            writeln!(f, "{line}|")?;
+            line.clear();
+        }
+        writeln!(f, "{}", horizontal_line)
+    }
+
+    /// Checks that no row, column or box repeats a number (nor a knight's move apart with the
+    /// anti-knight rule), empty cells are ignored
+    fn is_valid(&self) -> bool {
+        let units_ok = self.units().iter().all(|unit| {
+            let mut seen = 0u16;
+            unit.iter()
+                .filter_map(|&(i, j)| self.get(i, j).get_number())
+                .all(|x| {
+                    let repeated = seen & digit_bit(x) != 0;
+                    seen |= digit_bit(x);
+                    !repeated
+                })
+        });
+        units_ok
+            && (0..N).cartesian_product(0..N).all(|(i, j)| {
+                let x = self.get(i, j).get_number();
+                x.is_none_or(|x| !self.get_knight_values(i, j).contains(&x))
+            })
+    }
+}
+
+/// Features that only make sense for the standard 9x9 board
+impl Sudoku {
+    /// Index `k` counts the empty cells with exactly `k` candidates
+    pub fn candidate_histogram(&self) -> [usize; N + 1] {
+        let mut histogram = [0; N + 1];
+        for (i, j) in (0..N).cartesian_product(0..N) {
+            if self.get(i, j).get_number().is_none() {
+                histogram[self.get_guesses(i, j).len()] += 1;
+            }
+        }
+        histogram
+    }
+
+    /// Boards with fewer than 17 clues can never have a unique solution, this is a quick
+    /// check that doesn't need to search
+    pub fn is_under_clued(&self) -> bool {
+        self.clue_count() < MIN_UNIQUE_CLUES
+    }
+
+    /// Symmetries that map the complete grid onto itself up to relabeling the digits, the
+    /// `Identity` is always included. Returns nothing for incomplete grids.
+    pub fn solution_symmetries(&self) -> Vec<Symmetry> {
+        if self.filled_count() < N * N {
+            return Vec::new();
+        }
+        Symmetry::ALL
+            .into_iter()
+            .filter(|&symmetry| {
+                // The relabeling is forced cell by cell, it only has to stay consistent
+                let mut relabel = [0; N + 1];
+                (0..N).cartesian_product(0..N).all(|(i, j)| {
+                    let (ti, tj) = symmetry.apply(i, j);
+                    let from = self.get(i, j).get_number().unwrap() as usize;
+                    let to = self.get(ti, tj).get_number().unwrap();
+                    match relabel[from] {
+                        0 => {
+                            relabel[from] = to;
+                            true
+                        }
+                        x => x == to,
+                    }
+                })
+            })
+            .collect()
+    }
+
+    /// Canonical representative of the board: the lexicographically smallest (row-major, empty
+    /// cells as 0) among its images by every transformation that keeps a sudoku valid, that is
+    /// transposing, reordering the bands and stacks, reordering the rows inside each band and
+    /// the columns inside each stack, and relabeling the digits. Boards that are equivalent
+    /// under those transformations reduce to the same board. With the anti-knight rule only
+    /// the `Symmetry` transformations keep the board valid, so only those are used.
+    pub fn reduce(&self) -> Sudoku {
+        let flat = self.to_flat();
+        let smallest = match self.anti_knight {
+            true => Symmetry::ALL
+                .into_iter()
+                .map(|symmetry| {
+                    let mut image = vec![0; N * N];
+                    for (i, j) in (0..N).cartesian_product(0..N) {
+                        let (ti, tj) = symmetry.apply(i, j);
+                        image[ti * N + tj] = flat[i * N + j];
+                    }
+                    // Numbering the digits by order of appearance gives the smallest relabeling
+                    let mut relabel = [0; N + 1];
+                    let mut next = 0;
+                    for x in image.iter_mut().filter(|x| **x != 0) {
+                        if relabel[*x as usize] == 0 {
+                            next += 1;
+                            relabel[*x as usize] = next;
+                        }
+                        *x = relabel[*x as usize];
+                    }
+                    image
+                })
+                .min()
+                .unwrap(),
+            false => smallest_image(&flat).to_vec(),
+        };
+        let mut board = Sudoku::from_flat(&smallest).expect("symmetries keep the board valid");
+        board.anti_knight = self.anti_knight;
+        board
+    }
+
+    /// Checks the board is a legal puzzle with exactly one solution. Checks go from cheap to
+    /// expensive: constraints, clue count and then solution counting.
+    pub fn validate_puzzle(&self) -> Result<(), PuzzleError> {
+        if !self.is_valid() {
+            return Err(PuzzleError::InvalidConstraints);
+        }
+        if self.is_under_clued() {
+            return Err(PuzzleError::TooFewClues);
+        }
+        match count_solutions(self.clone(), 2) {
+            0 => Err(PuzzleError::NoSolution),
+            1 => Ok(()),
+            _ => Err(PuzzleError::MultipleSolutions),
+        }
+    }
+
+    /// Candidates of every cell in row-major order (index `i * 9 + j`), empty for cells with
+    /// a number
+    pub fn all_candidates(&self) -> [Vec<u16>; N * N] {
+        core::array::from_fn(|k| {
+            let (i, j) = (k / N, k % N);
+            match self.get(i, j) {
+                Item::Number(_) => Vec::new(),
+                _ => self.get_guesses(i, j),
+            }
+        })
+    }
+
+    /// Copy with the candidates of every cell without a number as `Item::Guesses`, cells
+    /// without candidates become `Item::Error`. Unlike `compute_guesses` nothing is filled in
+    /// (single candidates stay as guesses) and the state is left as it is.
+    pub fn with_all_candidates(&self) -> Sudoku {
+        let mut board = self.clone();
+        for (k, candidates) in self.all_candidates().into_iter().enumerate() {
+            let (i, j) = (k / N, k % N);
+            if self.get(i, j).get_number().is_none() {
+                board.rows[i][j] = match candidates.is_empty() {
+                    true => Item::Error,
+                    false => Item::Guesses(candidates),
+                };
+            }
+        }
+        board
+    }
+
+    /// FNV-1a hash of the cell values, stable across processes (ignores `state` and guesses)
+    pub fn stable_id(&self) -> u64 {
+        self.to_flat()
+            .into_iter()
+            .fold(0xcbf29ce484222325, |hash, x| {
+                (hash ^ x as u64).wrapping_mul(0x100000001b3)
+            })
+    }
+
+    /// Renders the board like `Display`, with column numbers (1-9) on top and row letters
+    /// (A-I) on the left
+    pub fn to_labeled_string(&self) -> String {
+        let mut grid = String::new();
+        self.write_grid(&mut grid, |_, n| digit_to_char(n).to_string())
+            .unwrap();
+        let mut s = format!("  {}\n", (1..=N).map(|j| format!(" {j}")).join(""));
+        let mut labels = ('A'..='I').take(N);
+        for line in grid.lines() {
+            match line.starts_with('|') {
+                true => s.push(labels.next().unwrap()),
+                false => s.push(' '),
+            }
+            s.push(' ');
+            s.push_str(line);
+            s.push('\n');
+        }
+        s
+    }
+}
+
+/// The 6 orders of three bands, stacks, or lines inside one of them
+const ORDERS: [[usize; 3]; 6] = [
+    [0, 1, 2],
+    [0, 2, 1],
+    [1, 0, 2],
+    [1, 2, 0],
+    [2, 0, 1],
+    [2, 1, 0],
+];
+
+/// Smallest image of a 9x9 grid (row-major, 0 for empty cells) for `Sudoku::reduce`. The
+/// columns are placed in each of their 2 * 6^4 ways (transposed or not, stack order and column
+/// order inside each stack), then `MinRows` picks the rows.
+fn smallest_image(flat: &[u8]) -> [u8; N * N] {
+    let mut search = MinRows {
+        rows: [[0; N]; N],
+        best: [u8::MAX; N * N],
+    };
+    for transposed in [false, true] {
+        let cell = |i: usize, j: usize| match transposed {
+            false => flat[i * N + j],
+            true => flat[j * N + i],
+        };
+        for stacks in ORDERS {
+            for inner in (0..3).map(|_| ORDERS).multi_cartesian_product() {
+                for (i, j) in (0..N).cartesian_product(0..N) {
+                    let stack = stacks[j / 3];
+                    search.rows[i][j] = cell(i, stack * 3 + inner[stack][j % 3]);
+                }
+                search.extend(0, 0, [false; N], [0; N + 1], 0);
+            }
+        }
+    }
+    search.best
+}
+
+/// Row by row search of the smallest image of a grid whose columns are already placed, the
+/// best image found so far is kept between grids
+struct MinRows {
+    rows: [[u8; N]; N],
+    best: [u8; N * N],
+}
+
+impl MinRows {
+    /// Tries every row the bands allow as row `k` of the image, `band` being the band of the
+    /// previous row and `used` the rows already placed. Digits are numbered by order of
+    /// appearance, which is the smallest relabeling. Branches that can't beat `best` are cut.
+    fn extend(&mut self, k: usize, band: usize, used: [bool; N], relabel: [u8; N + 1], next: u8) {
+        if k == N {
+            return;
+        }
+        let allowed = |r: usize| match k % 3 {
+            0 => (0..3).all(|t| !used[r / 3 * 3 + t]),
+            _ => r / 3 == band && !used[r],
+        };
+        for r in (0..N).filter(|&r| allowed(r)) {
+            // Rows that are the same in the same band lead to the same images
+            if (r / 3 * 3..r).any(|q| allowed(q) && self.rows[q] == self.rows[r]) {
+                continue;
+            }
+            let (mut relabel, mut next) = (relabel, next);
+            let mut row = [0; N];
+            for (out, &x) in row.iter_mut().zip(&self.rows[r]) {
+                if x != 0 {
+                    if relabel[x as usize] == 0 {
+                        next += 1;
+                        relabel[x as usize] = next;
+                    }
+                    *out = relabel[x as usize];
+                }
+            }
+            let best = &mut self.best[k * N..(k + 1) * N];
+            match row.as_slice().cmp(best) {
+                Ordering::Greater => continue,
+                Ordering::Equal => {}
+                Ordering::Less => {
+                    // Any image with this prefix beats the previous best
+                    best.copy_from_slice(&row);
+                    self.best[(k + 1) * N..].fill(u8::MAX);
+                }
+            }
+            let mut used = used;
+            used[r] = true;
+            self.extend(k + 1, r / 3, used, relabel, next);
+        }
+    }
+}
+
+impl core::fmt::Display for Item {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Item::Number(n) => write!(f, "{}", digit_to_char(*n)),
+            Item::Empty => write!(f, "."),
+            Item::Guesses(guesses) => write!(f, "[{}]", guesses.iter().join(",")),
+            Item::Error => write!(f, "X"),
+        }
+    }
+}
+
+impl<const N: usize> core::fmt::Display for Sudoku<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.write_grid(f, |_, n| digit_to_char(n).to_string())
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_sudoku_from_text_works() {
+        let text = " 1
+69  2  57
+    692
+  9   4
+47     2
 581 9   3
   5  86
  4 2  8 1
@@ -255,6 +1444,153 @@ mod tests {
         println!("{err}");
     }
 
+    #[test]
+    fn char_to_digit_works() {
+        let cases = [
+            ('7', 9, Some(7)),
+            ('a', 16, Some(10)),
+            ('B', 16, Some(11)),
+            ('g', 16, Some(16)),
+            ('G', 16, Some(16)),
+            ('h', 16, None),
+            ('A', 9, None),
+            ('0', 16, None),
+            ('.', 16, None),
+        ];
+        for (c, size, expected) in cases {
+            assert_eq!(char_to_digit(c, size), expected, "{c}");
+        }
+        for x in 1..=16 {
+            let c = digit_to_char(x);
+            assert!(!c.is_ascii_lowercase());
+            assert_eq!(char_to_digit(c, 16), Some(x));
+            assert_eq!(char_to_digit(c.to_ascii_lowercase(), 16), Some(x));
+        }
+        assert_eq!(
+            Sudoku::from_text("a").unwrap_err(),
+            "Invalid char: a".to_string()
+        );
+    }
+
+    #[test]
+    fn parse_16x16_works() {
+        let text = "123456789aBcDeFg
+
+    A
+
+g
+";
+        let shape = BoxShape {
+            width: 4,
+            height: 4,
+        };
+        let board = Sudoku::<16>::from_text_with_shape(text, shape).unwrap();
+        let first_row = (1..=16).map(Item::Number).collect_vec();
+        assert_eq!(board.rows[0].to_vec(), first_row);
+        assert_eq!(*board.get(2, 4), Item::Number(10));
+        assert_eq!(*board.get(4, 0), Item::Number(16));
+        assert_eq!(board.get_guesses(1, 0), (5..=15).collect_vec());
+        let rendered = board.to_string();
+        assert!(rendered.contains("|1 2 3 4|5 6 7 8|9 A B C|D E F G|"));
+
+        for bad in ["h", "12345678H"] {
+            let err = Sudoku::<16>::from_text_with_shape(bad, shape).unwrap_err();
+            assert!(err.starts_with("Invalid char"), "{bad}: {err}");
+        }
+        let err = Sudoku::<16>::from_text_with_shape("a\nA", shape).unwrap_err();
+        assert_eq!(err, "Duplicate 10 in box 0 at (0,0) and (1,0)");
+    }
+
+    #[test]
+    fn create_sudoku_from_text_never_panics() {
+        let long_line = "1".repeat(200);
+        let many_lines = "\n".repeat(20);
+        let cases = [
+            ("", true),
+            ("😀", false),
+            (" 1😀", false),
+            ("é", false),
+            (long_line.as_str(), false),
+            (many_lines.as_str(), false),
+            ("0", true),
+            ("\u{0}", false),
+            ("12345678\r\n", true),
+        ];
+        for (text, ok) in cases {
+            let res = Sudoku::from_text(text);
+            println!("{text:?} -> {:?}", res.as_ref().err());
+            assert_eq!(res.is_ok(), ok);
+        }
+        // Zeros are empty cells, so they don't repeat
+        assert_eq!(
+            Sudoku::from_text("030\n0").unwrap(),
+            Sudoku::from_text(" 3").unwrap()
+        );
+    }
+
+    #[test]
+    fn display_item_works() {
+        let cases = [
+            (Item::Number(5), "5"),
+            (Item::Empty, "."),
+            (Item::Guesses(vec![1, 4, 9]), "[1,4,9]"),
+            (Item::Error, "X"),
+        ];
+        for (item, expected) in cases {
+            assert_eq!(item.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn is_valid_works() {
+        let empty = Sudoku::from_text("").unwrap();
+        assert!(empty.is_valid());
+        let full = Sudoku::from_text(
+            "926817345
+851394726
+473265891
+685123479
+734589162
+219746538
+568472913
+342951687
+197638254",
+        )
+        .unwrap();
+        assert!(full.is_valid());
+        let cases = [
+            ((0, 0), (0, 8), 1), // row
+            ((0, 0), (8, 0), 1), // column
+            ((3, 3), (4, 4), 5), // box 4
+            ((6, 6), (8, 8), 9), // box 8
+        ];
+        for ((i0, j0), (i, j), x) in cases {
+            let mut board = empty.clone();
+            board.set(i0, j0, x);
+            assert!(board.is_valid());
+            board.set(i, j, x);
+            assert!(!board.is_valid(), "duplicate at {:?} not detected", (i, j));
+        }
+    }
+
+    #[test]
+    fn create_sudoku_from_text_fails_on_box_duplicates() {
+        let text = "
+
+
+   5
+    5";
+        let err = Sudoku::from_text(text).unwrap_err();
+        println!("{err}");
+        assert_eq!(err, "Duplicate 5 in box 4 at (3,3) and (4,4)");
+        let text = "\n\n\n   5\n     5";
+        let err = Sudoku::from_text(text).unwrap_err();
+        assert_eq!(err, "Duplicate 5 in box 4 at (3,3) and (4,5)");
+        // Repeats in rows or columns keep the generic error
+        let err = Sudoku::from_text("5       5").unwrap_err();
+        assert_eq!(err, "Invalid board");
+    }
+
     #[test]
     fn get_row_values_works() {
         let text = "926817345
@@ -289,6 +1625,85 @@ mod tests {
         assert_eq!(col, expected);
     }
 
+    #[test]
+    fn box_shape_works() {
+        let shape = BoxShape {
+            width: 3,
+            height: 2,
+        };
+        assert_eq!(shape.size(), 6);
+        assert_eq!(
+            shape.cells(0),
+            vec![(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2)]
+        );
+        assert_eq!(shape.cells(3)[0], (2, 3));
+        assert_eq!(shape.cells(5)[5], (5, 5));
+        let covered = (0..6).flat_map(|b| shape.cells(b)).sorted().collect_vec();
+        assert_eq!(covered, (0..6).cartesian_product(0..6).collect_vec());
+        for b in 0..6 {
+            assert!(shape
+                .cells(b)
+                .iter()
+                .all(|&(i, j)| shape.index_of(i, j) == b));
+        }
+        let board = Sudoku::from_text("").unwrap();
+        assert_eq!(BOX.cells(4), board.box_cells(4));
+    }
+
+    #[test]
+    fn solve_6x6_works() {
+        let text = "    5
+4    3
+2    4
+ 6   1
+3 2  5
+ 4 3";
+        let shape = BoxShape {
+            width: 3,
+            height: 2,
+        };
+        let board = Sudoku::<6>::from_text_with_shape(text, shape).unwrap();
+        assert_eq!(board.get_square_values(0, 0), vec![4]);
+        assert_eq!(board.get_square_values(3, 3), vec![4, 1]);
+        assert_eq!(board.get_guesses(0, 0), vec![1, 6]);
+        assert_eq!(count_solutions(board.clone(), 2), 1);
+        let (solution, _) = solve_sudoku(board).unwrap();
+        let expected = "123456
+456123
+231564
+564231
+312645
+645312";
+        let expected = Sudoku::<6>::from_text_with_shape(expected, shape).unwrap();
+        assert!(expected.is_solved());
+        assert_eq!(solution, expected);
+
+        let err = Sudoku::<6>::from_text_with_shape(text, BOX).unwrap_err();
+        assert_eq!(err, "Boxes of 3x3 don't fit a board of size 6");
+        let tall = BoxShape {
+            width: 2,
+            height: 3,
+        };
+        assert!(Sudoku::<6>::from_text_with_shape("12\n\n\n 1", tall).is_ok());
+        assert!(Sudoku::<6>::from_text_with_shape("12\n\n 1", tall).is_err());
+
+        // Candidates don't fit a `u16` mask past 16 values
+        let wide = BoxShape {
+            width: 5,
+            height: 4,
+        };
+        let err = Sudoku::<20>::from_text_with_shape("1", wide).unwrap_err();
+        assert_eq!(
+            err,
+            "Boards of size 20 are not supported, the size goes from 1 to 16"
+        );
+        let empty = BoxShape {
+            width: 0,
+            height: 0,
+        };
+        assert!(Sudoku::<0>::from_text_with_shape("", empty).is_err());
+    }
+
     #[test]
     fn get_square_values_works() {
         let text = "926817345
@@ -330,6 +1745,74 @@ mod tests {
         assert_eq!(guesses, expected);
     }
 
+    #[test]
+    fn anti_knight_works() {
+        let board = Sudoku::builder().set(4, 4, 5).build().unwrap();
+        assert!(board.get_guesses(2, 3).contains(&5));
+        let board = board.with_anti_knight().unwrap();
+        assert!(!board.get_guesses(2, 3).contains(&5));
+        assert!(!board.get_guesses(6, 5).contains(&5));
+        assert!(board.get_guesses(2, 2).contains(&5));
+        assert!(board.snapshot().with_anti_knight().is_ok());
+
+        let mut conflict = board.clone();
+        conflict.set(2, 3, 5);
+        assert!(!conflict.is_valid());
+        let conflict = Sudoku::builder().set(4, 4, 5).set(2, 3, 5).build().unwrap();
+        assert!(conflict.is_valid());
+        assert!(conflict.with_anti_knight().is_err());
+
+        let (solution, _) = solve_sudoku(board).unwrap();
+        assert!(solution.is_valid());
+        for (i, j) in (0..N).cartesian_product(0..N) {
+            let x = solution.get(i, j).get_number().unwrap();
+            assert!(Sudoku::<N>::knight_cells(i, j)
+                .all(|(ki, kj)| *solution.get(ki, kj) != Item::Number(x)));
+        }
+    }
+
+    #[test]
+    fn get_guesses_shuffled_works() {
+        use crate::rng::SplitMix64;
+        let board = Sudoku::from_text("").unwrap();
+        let shuffled = board.get_guesses_shuffled(0, 0, &mut SplitMix64::new(42));
+        assert_eq!(shuffled, vec![8, 5, 9, 3, 6, 7, 1, 4, 2]);
+        assert_eq!(
+            shuffled,
+            board.get_guesses_shuffled(0, 0, &mut SplitMix64::new(42))
+        );
+        assert_ne!(shuffled, board.get_guesses(0, 0));
+        assert_eq!(
+            shuffled.iter().sorted().copied().collect_vec(),
+            board.get_guesses(0, 0)
+        );
+    }
+
+    #[test]
+    fn exclude_candidate_works() {
+        let mut board = Sudoku::from_text("").unwrap();
+        board.exclude_candidate(0, 0, 5).unwrap();
+        assert_eq!(board.get_guesses(0, 0), vec![1, 2, 3, 4, 6, 7, 8, 9]);
+        assert_eq!(board.get_guesses(0, 1), (1..=9).collect_vec());
+        let invalid = ["Invalid value: 0", "Invalid value: 10", "Invalid value: 16"];
+        for (value, err) in [0, 10, 16].into_iter().zip(invalid) {
+            assert_eq!(board.exclude_candidate(0, 0, value), Err(err.to_string()));
+        }
+        assert_eq!(
+            board.exclude_candidate(9, 0, 5),
+            Err("Cell (9,0) is out of the board".to_string())
+        );
+        assert_eq!(board.get_guesses(0, 0), vec![1, 2, 3, 4, 6, 7, 8, 9]);
+
+        // Technique eliminations don't mix with the user's exclusions
+        let mut board = Sudoku::from_text("  3\n456\n789").unwrap();
+        board.exclude_candidate(0, 8, 9).unwrap();
+        board.propagate();
+        assert_eq!(board.excluded[0][8], digit_bit(9));
+        assert_eq!(board.eliminated[0][8], digit_bit(1) | digit_bit(2));
+        assert!(!board.get_guesses(0, 8).contains(&9));
+    }
+
     #[test]
     fn compute_guesses_works() {
         let text = "926817 45
@@ -351,4 +1834,888 @@ mod tests {
             unreachable!();
         }
     }
+
+    #[test]
+    fn observer_works() {
+        use crate::solver::solve_sudoku_parallel;
+        use std::sync::{Arc, Mutex};
+
+        let mut board = Sudoku::from_text("").unwrap();
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let log = changes.clone();
+        board.set_observer(Box::new(move |i, j, item| {
+            log.lock().unwrap().push((i, j, item.clone()))
+        }));
+        board.set(2, 5, 7);
+        board.set(2, 5, 7);
+        board.clear(2, 5).unwrap();
+        board.clone().set(0, 0, 1);
+        assert_eq!(
+            *changes.lock().unwrap(),
+            vec![(2, 5, Item::Number(7)), (2, 5, Item::Empty)]
+        );
+        assert_eq!(board, Sudoku::from_text("").unwrap());
+
+        // Observed boards can still go to the parallel solvers
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Sudoku>();
+        let (solution, _) = solve_sudoku_parallel(board).unwrap();
+        assert!(solution.is_solved());
+        assert_eq!(changes.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn clue_count_works() {
+        let text = "  3 2 6
+9  3 5  1
+  18 64
+  81 29
+7       8
+  67 82
+  26 95
+8  2 3  9
+  5 1 3";
+        let board = Sudoku::from_text(text).unwrap();
+        let keep_clues = |count: usize| {
+            let mut kept = 0;
+            let data = board
+                .to_flat()
+                .into_iter()
+                .map(|x| {
+                    if x != 0 && kept < count {
+                        kept += 1;
+                        x
+                    } else {
+                        0
+                    }
+                })
+                .collect_vec();
+            Sudoku::from_flat(&data).unwrap()
+        };
+        let sparse = keep_clues(16);
+        assert_eq!(sparse.clue_count(), 16);
+        assert!(sparse.is_under_clued());
+        let dense = keep_clues(30);
+        assert_eq!(dense.clue_count(), 30);
+        assert!(!dense.is_under_clued());
+    }
+
+    #[test]
+    fn complete_board_is_solved_on_parse() {
+        let text = "926817345
+851394726
+473265891
+685123479
+734589162
+219746538
+568472913
+342951687
+197638254";
+        assert!(Sudoku::from_text(text).unwrap().is_solved());
+        let partial = text.replacen('9', " ", 1);
+        assert!(!Sudoku::from_text(&partial).unwrap().is_solved());
+    }
+
+    #[test]
+    fn candidate_cells_works() {
+        let text = "926817 45
+8 139 726
+4  26 891
+6 5   47
+73  8 1 2
+2 97465 8
+    72
+ 42  1  7
+1 76 8  4";
+        let board = Sudoku::from_text(text).unwrap();
+        let cells = board.candidate_cells(3);
+        println!("{cells:?}");
+        assert!(cells.contains(&(0, 6)));
+        assert!(cells.contains(&(6, 8)));
+        for &(i, j) in &cells {
+            assert!(board.get(i, j).get_number().is_none());
+            assert!(board.get_guesses(i, j).contains(&3));
+        }
+        let expected = (0..N)
+            .cartesian_product(0..N)
+            .filter(|&(i, j)| board.all_candidates()[i * N + j].contains(&3))
+            .collect_vec();
+        assert_eq!(cells, expected);
+    }
+
+    #[test]
+    fn all_candidates_works() {
+        let text = "926817 45
+8 139 726
+4  26 891
+6 5   47
+73  8 1 2
+2 97465 8
+    72
+ 42  1  7
+1 76 8  4";
+        let board = Sudoku::from_text(text).unwrap();
+        let candidates = board.all_candidates();
+        for (i, j) in (0..N).cartesian_product(0..N) {
+            match board.get(i, j) {
+                Item::Number(_) => assert!(candidates[i * 9 + j].is_empty()),
+                _ => assert_eq!(candidates[i * 9 + j], board.get_guesses(i, j)),
+            }
+        }
+        assert_eq!(candidates[6 * 9 + 8], vec![3, 9]);
+    }
+
+    #[test]
+    fn snapshot_works() {
+        let text = "926817 45
+8 139 726
+4  26 891
+6 5   47
+73  8 1 2
+2 97465 8
+    72
+ 42  1  7
+1 76 8  4";
+        let original = Sudoku::from_text(text).unwrap();
+        let mut board = original.clone();
+        board.compute_guesses();
+        board.exclude_candidate(6, 8, 3).unwrap();
+        board.rows[6][0] = Item::Error;
+        let snapshot = board.snapshot();
+        assert_eq!(snapshot.state, SudokuState::Unknown);
+        assert!(snapshot
+            .rows
+            .iter()
+            .flatten()
+            .all(|x| matches!(x, Item::Number(_) | Item::Empty)));
+        assert_eq!(snapshot.to_flat(), board.to_flat());
+        assert_eq!(*snapshot.get(6, 0), Item::Empty);
+        assert_eq!(snapshot.get_guesses(6, 8), vec![3, 9]);
+        assert_ne!(snapshot, original);
+    }
+
+    #[test]
+    fn is_givens_subset_of_works() {
+        let text = " 1
+69  2  57
+    692
+  9   4
+47     2
+581 9   3
+  5  86
+ 4 2  8 1
+   6   4";
+        let puzzle = Sudoku::from_text(text).unwrap();
+        let solution = puzzle.solved().unwrap();
+        assert!(puzzle.is_givens_subset_of(&solution));
+        assert!(solution.is_givens_subset_of(&solution));
+        assert!(!solution.is_givens_subset_of(&puzzle));
+        // The solution has 8 at (0, 0)
+        let mismatched = Sudoku::from_text(&text.replacen(' ', "3", 1)).unwrap();
+        assert!(!mismatched.is_givens_subset_of(&solution));
+    }
+
+    #[test]
+    fn hamming_works() {
+        let text = " 1
+69  2  57
+    692
+  9   4
+47     2
+581 9   3
+  5  86
+ 4 2  8 1
+   6   4";
+        let puzzle = Sudoku::from_text(text).unwrap();
+        let solution = puzzle.solved().unwrap();
+        assert_eq!(solution.hamming(&solution), 0);
+        let mut changed = solution.clone();
+        changed.set(0, 0, 9);
+        assert_eq!(solution.hamming(&changed), 1);
+        changed.clear(0, 1).unwrap();
+        assert_eq!(changed.hamming(&solution), 2);
+        assert_eq!(puzzle.hamming(&solution), 81 - puzzle.clue_count());
+    }
+
+    #[test]
+    fn removal_impact_works() {
+        let text = " 1
+69  2  57
+    692
+  9   4
+47     2
+581 9   3
+  5  86
+ 4 2  8 1
+   6   4";
+        let mut board = Sudoku::from_text(text).unwrap();
+        let solution = board.solved().unwrap();
+        // (0, 0) is not needed for a unique solution
+        board.set(0, 0, solution.get(0, 0).get_number().unwrap());
+        let impact = board.removal_impact();
+        println!("{impact:?}");
+        assert_eq!(impact.len(), board.clue_count());
+        assert!(impact.contains(&((0, 0), 1)));
+        assert!(impact.iter().all(|&(_, count)| count == 1 || count == 2));
+        assert!(!board.is_minimal());
+    }
+
+    #[test]
+    fn solution_eq_works() {
+        let text = " 1
+69  2  57
+    692
+  9   4
+47     2
+581 9   3
+  5  86
+ 4 2  8 1
+   6   4";
+        let solution = Sudoku::from_text(text).unwrap().solved().unwrap();
+        let mut other = Sudoku::from_flat(&solution.to_flat()).unwrap();
+        assert!(solution.solution_eq(&other));
+        other.rows[0][0] = Item::Guesses(vec![8]);
+        assert_eq!(solution.to_flat()[1..], other.to_flat()[1..]);
+        assert!(!solution.solution_eq(&other));
+        assert!(!other.solution_eq(&other));
+    }
+
+    #[test]
+    fn box_clue_counts_works() {
+        let text = "  3 2 6
+9  3 5  1
+  18 64
+  81 29
+7       8
+  67 82
+  26 95
+8  2 3  9
+  5 1 3";
+        let board = Sudoku::from_text(text).unwrap();
+        assert_eq!(board.box_clue_counts(), [3, 5, 3, 3, 4, 3, 3, 5, 3]);
+        let total: u8 = board.box_clue_counts().iter().sum();
+        assert_eq!(total as usize, board.clue_count());
+    }
+
+    #[test]
+    fn compute_guesses_tracked_works() {
+        let text = "926817 45
+8 139 726
+4  26 891
+6 5   47
+73  8 1 2
+2 97465 8
+    72  
+ 42  1  7
+1 76 8  4";
+        let mut board = Sudoku::from_text(text).unwrap();
+        let before = board.clone();
+        let changed = board.compute_guesses_tracked();
+        println!("{changed:?}");
+        let expected = (0..N)
+            .cartesian_product(0..N)
+            .filter(|&(i, j)| before.get(i, j) != board.get(i, j))
+            .collect_vec();
+        assert_eq!(changed, expected);
+        assert!(changed.contains(&(0, 6)));
+        assert_eq!(*board.get(0, 6), Item::Number(3));
+        assert!(changed
+            .iter()
+            .any(|&(i, j)| matches!(board.get(i, j), Item::Guesses(_))));
+    }
+
+    #[test]
+    fn entropy_works() {
+        let near_solved = Sudoku::from_text(
+            "926817345
+851394726
+473265891
+685123479
+734589162
+219746538
+5  472 1
+342951687
+197638254",
+        )
+        .unwrap();
+        let empty = Sudoku::from_text("").unwrap();
+        let (low, high) = (near_solved.entropy(), empty.entropy());
+        println!("near solved: {low}, empty: {high}");
+        assert!(low < high);
+        assert!((high - 81.0 * 9f64.log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn apply_moves_works() {
+        let mut board = Sudoku::from_text(
+            "926817345
+851394726
+473265891
+685123479
+734589162
+219746538
+5  472 1
+342951687
+197638254",
+        )
+        .unwrap();
+        let moves = [(6, 1, 6), (6, 2, 8), (6, 6, 9), (6, 8, 3)];
+        board.apply_moves(&moves).unwrap();
+        assert_eq!(board.get_row_values(6), vec![5, 6, 8, 4, 7, 2, 9, 1, 3]);
+    }
+
+    #[test]
+    fn apply_moves_fails_on_illegal_move() {
+        let mut board = Sudoku::from_text(
+            "926817345
+851394726
+473265891
+685123479
+734589162
+219746538
+5  472 1
+342951687
+197638254",
+        )
+        .unwrap();
+        let moves = [(6, 1, 6), (6, 2, 6), (6, 6, 9)];
+        let (idx, err) = board.apply_moves(&moves).unwrap_err();
+        println!("{err}");
+        assert_eq!(idx, 1);
+        assert_eq!(*board.get(6, 1), Item::Number(6));
+        assert_eq!(*board.get(6, 2), Item::Empty);
+    }
+
+    #[test]
+    fn format_with_conflicts_works() {
+        colored::control::set_override(true);
+        let text = "  3 2 6
+9  3 5  1
+  18 64
+  81 29
+7       8
+  67 82
+  26 95
+8  2 3  9
+  5 1 3";
+        let mut board = Sudoku::from_text(text).unwrap();
+        assert!(board.find_conflicts().is_empty());
+        board.set(0, 0, 3);
+        assert_eq!(board.find_conflicts(), vec![(0, 0), (0, 2)]);
+        let output = board.format_with_conflicts();
+        println!("{output}");
+        let marked = "3".on_red().to_string();
+        assert_eq!(output.matches(&marked).count(), 2);
+        assert!(output
+            .lines()
+            .nth(1)
+            .unwrap()
+            .starts_with(&format!("|{marked} ")));
+    }
+
+    #[test]
+    fn to_overlay_string_works() {
+        colored::control::set_override(true);
+        let text = "  3 2 6
+9  3 5  1
+  18 64
+  81 29
+7       8
+  67 82
+  26 95
+8  2 3  9
+  5 1 3";
+        let board = Sudoku::from_text(text).unwrap();
+        let solution = board.solved().unwrap();
+        let output = solution.to_overlay_string(&board);
+        println!("{output}");
+        let given = "3".cyan().to_string();
+        let filled = "4".yellow().to_string();
+        assert!(output
+            .lines()
+            .nth(1)
+            .unwrap()
+            .starts_with(&format!("|{filled} {} {given}", "8".yellow())));
+        assert_eq!(output.matches("\u{1b}[36m").count(), board.clue_count());
+        assert_eq!(
+            output.matches("\u{1b}[33m").count(),
+            81 - board.clue_count()
+        );
+    }
+
+    #[test]
+    fn complete_units_works() {
+        let text = "926817345
+851394726
+473265891
+685123479
+734589162
+219746538
+5  472 1
+342951687
+197638254";
+        let board = Sudoku::from_text(text).unwrap();
+        let complete = board.complete_units();
+        let mut expected = [0, 1, 2, 3, 4, 5, 7, 8]
+            .into_iter()
+            .map(|i| (UnitKind::Row, i))
+            .collect_vec();
+        expected.extend([0, 3, 4, 5, 7].into_iter().map(|j| (UnitKind::Column, j)));
+        expected.extend(
+            [0, 1, 2, 3, 4, 5, 7]
+                .into_iter()
+                .map(|b| (UnitKind::Box, b)),
+        );
+        assert_eq!(complete, expected);
+    }
+
+    #[test]
+    fn lock_works() {
+        let mut board = Sudoku::from_text("  3 2 6").unwrap();
+        board.lock_givens();
+        assert_eq!(board.clear(0, 2), Err("Cell (0,2) is locked".to_string()));
+        assert_eq!(
+            board.try_set(0, 2, 3),
+            Err("Cell (0,2) is locked".to_string())
+        );
+        assert_eq!(*board.get(0, 2), Item::Number(3));
+        board.try_set(0, 0, 1).unwrap();
+        board.clear(0, 0).unwrap();
+        assert_eq!(*board.get(0, 0), Item::Empty);
+        board.lock(0, 0).unwrap();
+        assert!(board.try_set(0, 0, 1).is_err());
+        assert_eq!(*board.get(0, 0), Item::Empty);
+
+        let out = Err("Cell (9,9) is out of the board".to_string());
+        assert_eq!(board.clear(9, 9), out);
+        assert_eq!(board.lock(9, 9), out);
+        assert_eq!(
+            board.clear(0, 9),
+            Err("Cell (0,9) is out of the board".to_string())
+        );
+    }
+
+    #[test]
+    fn clear_resets_eliminations() {
+        let text =
+            "001090000400080300750300000080031000000029100090700000500000006000006840028000900";
+        let mut board = Sudoku::from_sudokuwiki(text).unwrap();
+        board.propagate();
+        board.clear(5, 6).unwrap();
+        assert_eq!(board.get_guesses(5, 6), board.snapshot().get_guesses(5, 6));
+        assert_eq!(board.get_guesses(5, 6), vec![2, 5, 6]);
+
+        let mut board = Sudoku::from_sudokuwiki(text).unwrap();
+        board.propagate();
+        // Overwriting a number also drops what was derived from it
+        board.set(5, 6, 5);
+        let snapshot = board.snapshot();
+        for (i, j) in (0..N).cartesian_product(0..N) {
+            assert_eq!(board.get_guesses(i, j), snapshot.get_guesses(i, j));
+        }
+    }
+
+    #[test]
+    fn dead_cells_works() {
+        let text = " 23456789
+
+
+1";
+        let mut board = Sudoku::from_text(text).unwrap();
+        assert_eq!(board.dead_cells(), vec![(0, 0)]);
+        board.compute_guesses_tracked();
+        assert_eq!(board.dead_cells(), vec![(0, 0)]);
+        let board = Sudoku::from_text(" 23456789").unwrap();
+        assert!(board.dead_cells().is_empty());
+    }
+
+    #[test]
+    fn find_unsolvable_unit_works() {
+        let text = " 23456789
+
+
+
+1";
+        let board = Sudoku::from_text(text).unwrap();
+        assert_eq!(board.find_unsolvable_unit(), Some((UnitKind::Row, 0, 1)));
+        assert!(board.solved().is_err());
+        let text = " 23456789";
+        let board = Sudoku::from_text(text).unwrap();
+        assert_eq!(board.find_unsolvable_unit(), None);
+    }
+
+    #[test]
+    fn all_conflicts_works() {
+        let text = "926817345
+851394726
+473265891
+685123479
+734589162
+219746538
+568472913
+342951687
+197638254";
+        let mut board = Sudoku::from_text(text).unwrap();
+        assert!(board.all_conflicts().is_empty());
+        // Swapping two cells of a row and box repeats numbers in both columns
+        board.set(0, 0, 2);
+        board.set(0, 1, 9);
+        let expected = HashSet::from([(0, 0), (5, 0), (0, 1), (8, 1)]);
+        assert_eq!(board.all_conflicts(), expected);
+    }
+
+    #[test]
+    fn overfilled_works() {
+        let text = "  3 2 6
+9  3 5  1
+  18 64
+  81 29
+7       8
+  67 82
+  26 95
+8  2 3  9
+  5 1 3";
+        let mut board = Sudoku::from_text(text).unwrap();
+        assert!(board.overfilled().is_empty());
+        board.set(0, 0, 3);
+        assert_eq!(board.overfilled(), vec![((0, 0), 3), ((0, 2), 3)]);
+    }
+
+    #[test]
+    fn with_all_candidates_works() {
+        let text = " 26817345
+851394726
+473265891
+685123479
+734589162
+219746538
+5  472 1
+342951687
+197638254";
+        let board = Sudoku::from_text(text).unwrap();
+        let marked = board.with_all_candidates();
+        println!("{marked}");
+        assert_eq!(*marked.get(0, 0), Item::Guesses(vec![9]));
+        assert_eq!(*marked.get(6, 1), Item::Guesses(vec![6]));
+        assert_eq!(*marked.get(1, 1), *board.get(1, 1));
+        assert_eq!(marked.state, board.state);
+        assert_eq!(marked.filled_count(), board.filled_count());
+        let dead = Sudoku::from_text(" 23456789\n\n\n\n1").unwrap();
+        assert_eq!(*dead.with_all_candidates().get(0, 0), Item::Error);
+    }
+
+    #[test]
+    fn unit_fill_fractions_works() {
+        let text = "  3 2 6
+9  3 5  1
+  18 64
+  81 29
+7       8
+  67 82
+  26 95
+8  2 3  9
+  5 1 3";
+        let mut board = Sudoku::from_text(text).unwrap();
+        for (j, x) in [(0, 4), (1, 8), (3, 9), (5, 1), (7, 5), (8, 7)] {
+            board.set(0, j, x);
+        }
+        let (rows, cols, boxes) = board.unit_fill_fractions();
+        println!("{rows:?}\n{cols:?}\n{boxes:?}");
+        assert_eq!(rows[0], 1.0);
+        assert_eq!(rows[4], 2.0 / 9.0);
+        assert_eq!(cols[0], 4.0 / 9.0);
+        assert_eq!(boxes[0], 5.0 / 9.0);
+        let (rows, cols, boxes) = Sudoku::from_text("").unwrap().unit_fill_fractions();
+        assert!(rows.into_iter().chain(cols).chain(boxes).all(|x| x == 0.0));
+    }
+
+    #[test]
+    fn candidate_histogram_works() {
+        let text = "      39
+6       7
+ 571
+     74 5
+  3 1
+58  927
+13
+ 46  5 3
+  8";
+        let board = Sudoku::from_text(text).unwrap();
+        let histogram = board.candidate_histogram();
+        println!("{histogram:?}");
+        assert_eq!(histogram.iter().sum::<usize>(), N * N - board.clue_count());
+        assert_eq!(histogram[0], 0);
+        let empty = Sudoku::from_text("").unwrap();
+        assert_eq!(empty.candidate_histogram()[9], 81);
+        let dead = Sudoku::from_text(" 23456789\n\n\n\n1").unwrap();
+        assert_eq!(dead.candidate_histogram()[0], 1);
+    }
+
+    #[test]
+    fn is_solvable_works() {
+        let easy = Sudoku::from_text(
+            "  3 2 6
+9  3 5  1
+  18 64
+  81 29
+7       8
+  67 82
+  26 95
+8  2 3  9
+  5 1 3",
+        )
+        .unwrap();
+        let hard = Sudoku::from_text(
+            "       9
+   8  1
+  7   2
+   3 7
+   51
+58149 76
+  5     2
+9 6    3
+ 2  3 54",
+        )
+        .unwrap();
+        let mut unsolvable = easy.clone();
+        unsolvable.set(0, 0, 5);
+        let mut repeated = easy.clone();
+        repeated.set(0, 0, 3);
+        let boards = [
+            easy,
+            hard,
+            unsolvable,
+            repeated,
+            Sudoku::from_text("").unwrap(),
+            Sudoku::from_text(" 23456789\n\n\n\n1").unwrap(),
+        ];
+        for board in boards {
+            let expected = solve_sudoku(board.clone()).is_ok();
+            assert_eq!(board.is_solvable(), expected, "{board}");
+        }
+    }
+
+    #[test]
+    fn solution_symmetries_works() {
+        let symmetric = Sudoku::from_text(
+            "123456789
+456789123
+789123456
+234567891
+567891234
+891234567
+345678912
+678912345
+912345678",
+        )
+        .unwrap();
+        let symmetries = symmetric.solution_symmetries();
+        assert!(symmetries.contains(&Symmetry::Rotate180));
+        assert!(symmetries.len() > 1);
+        let full = Sudoku::from_text(
+            "926817345
+851394726
+473265891
+685123479
+734589162
+219746538
+568472913
+342951687
+197638254",
+        )
+        .unwrap();
+        assert_eq!(full.solution_symmetries(), vec![Symmetry::Identity]);
+        let empty = Sudoku::from_text("").unwrap();
+        assert!(empty.solution_symmetries().is_empty());
+    }
+
+    #[test]
+    fn reduce_works() {
+        let text = "  3 2 6
+9  3 5  1
+  18 64
+  81 29
+7       8
+  67 82
+  26 95
+8  2 3  9
+  5 1 3";
+        let board = Sudoku::from_text(text).unwrap();
+        let reflected = Sudoku::from_text(
+            &text
+                .lines()
+                .map(|line| format!("{line:9}").chars().rev().collect::<String>())
+                .join("\n"),
+        )
+        .unwrap();
+        assert_ne!(board, reflected);
+        let reduced = board.reduce();
+        println!("{reduced}");
+        assert_eq!(reflected.reduce(), reduced);
+        assert_eq!(reduced.reduce(), reduced);
+        assert_eq!(reduced.clue_count(), board.clue_count());
+        let relabeled =
+            Sudoku::from_text(&text.replace('3', "x").replace('1', "3").replace('x', "1")).unwrap();
+        assert_eq!(relabeled.reduce(), reduced);
+        let first = reduced.to_flat().into_iter().find(|&x| x != 0);
+        assert_eq!(first, Some(1));
+
+        // Reordering rows inside a band, or the bands, isn't a symmetry of the square
+        let lines = text.lines().collect_vec();
+        for order in [[1, 0, 2, 3, 4, 5, 6, 7, 8], [6, 7, 8, 3, 4, 5, 2, 0, 1]] {
+            let swapped = order.map(|k| lines[k]).join("\n");
+            let swapped = Sudoku::from_text(&swapped).unwrap();
+            assert_eq!(swapped.reduce(), reduced);
+        }
+        let flat = board.to_flat();
+        let transposed = (0..N * N).map(|k| flat[k % N * N + k / N]).collect_vec();
+        let transposed = Sudoku::from_flat(&transposed).unwrap();
+        assert_eq!(transposed.reduce(), reduced);
+        let other = Sudoku::from_text(" 1\n69  2  57").unwrap();
+        assert_ne!(other.reduce(), reduced);
+
+        // Row swaps would break the anti-knight rule, only symmetries of the square are used
+        let knight = Sudoku::from_text("1").unwrap().with_anti_knight().unwrap();
+        let swapped = Sudoku::from_text("\n1")
+            .unwrap()
+            .with_anti_knight()
+            .unwrap();
+        assert!(knight.reduce().anti_knight);
+        assert_ne!(swapped.reduce(), knight.reduce());
+    }
+
+    #[test]
+    fn validate_puzzle_works() {
+        let text = "  3 2 6
+9  3 5  1
+  18 64
+  81 29
+7       8
+  67 82
+  26 95
+8  2 3  9
+  5 1 3";
+        let board = Sudoku::from_text(text).unwrap();
+        assert_eq!(board.validate_puzzle(), Ok(()));
+        let mut repeated = board.clone();
+        repeated.set(0, 0, 3);
+        assert_eq!(
+            repeated.validate_puzzle(),
+            Err(PuzzleError::InvalidConstraints)
+        );
+        let mut ambiguous = board.clone();
+        for (i, j) in [(0, 2), (0, 4), (0, 6), (1, 0)] {
+            ambiguous.clear(i, j).unwrap();
+        }
+        assert_eq!(
+            ambiguous.validate_puzzle(),
+            Err(PuzzleError::MultipleSolutions)
+        );
+        let mut unsolvable = board.clone();
+        unsolvable.set(0, 0, 5);
+        assert_eq!(unsolvable.validate_puzzle(), Err(PuzzleError::NoSolution));
+        let sparse = Sudoku::from_text("1\n 2\n  3").unwrap();
+        assert_eq!(sparse.validate_puzzle(), Err(PuzzleError::TooFewClues));
+    }
+
+    #[test]
+    fn to_unicode_string_works() {
+        let board = Sudoku::from_text("  3 2 6\n9  3 5  1").unwrap();
+        let s = board.to_unicode_string();
+        println!("{s}");
+        let lines = s.lines().collect_vec();
+        assert_eq!(lines.len(), 2 * N + 1);
+        assert_eq!(lines[0], "┏━┯━┯━┳━┯━┯━┳━┯━┯━┓");
+        assert_eq!(lines[1], "┃ │ │3┃ │2│ ┃6│ │ ┃");
+        assert_eq!(lines[2], "┠─┼─┼─╂─┼─┼─╂─┼─┼─┨");
+        assert_eq!(lines[3], "┃9│ │ ┃3│ │5┃ │ │1┃");
+        assert_eq!(lines[6], "┣━┿━┿━╋━┿━┿━╋━┿━┿━┫");
+        assert_eq!(lines[18], "┗━┷━┷━┻━┷━┷━┻━┷━┷━┛");
+        assert!(lines.iter().all(|line| line.chars().count() == 2 * N + 1));
+    }
+
+    #[test]
+    fn to_labeled_string_works() {
+        let text = "  3 2 6
+9  3 5  1
+  18 64
+  81 29
+7       8
+  67 82
+  26 95
+8  2 3  9
+  5 1 3";
+        let labeled = Sudoku::from_text(text).unwrap().to_labeled_string();
+        println!("{labeled}");
+        let lines = labeled.lines().collect_vec();
+        assert_eq!(lines[0], "   1 2 3 4 5 6 7 8 9");
+        assert_eq!(lines[1], "   ----------------- ");
+        assert!(lines[2].starts_with("A |"));
+        assert!(lines[3].starts_with("B |9"));
+        assert!(lines.last().unwrap().starts_with("   -"));
+        assert_eq!(lines.iter().filter(|line| line.contains('|')).count(), 9);
+    }
+
+    #[test]
+    fn stable_id_works() {
+        let text = " 1
+69  2  57
+    692
+  9   4
+47     2
+581 9   3
+  5  86
+ 4 2  8 1
+   6   4";
+        let board = Sudoku::from_text(text).unwrap();
+        let mut same = Sudoku::from_text(text).unwrap();
+        same.state = SudokuState::Invalid;
+        let mut other = board.clone();
+        other.set(0, 0, 2);
+        assert_eq!(board.stable_id(), same.stable_id());
+        assert_ne!(board.stable_id(), other.stable_id());
+        assert_eq!(
+            Sudoku::from_text("").unwrap().stable_id(),
+            0x0edb_e9ed_be9a_769f
+        );
+    }
+
+    #[test]
+    fn branch_count_works() {
+        let text = " 1
+69  2  57
+    692
+  9   4
+47     2
+581 9   3
+  5  86
+ 4 2  8 1
+   6   4";
+        let mut board = Sudoku::from_text(text).unwrap();
+        assert_eq!(board.branch_count(), 0);
+        board.compute_guesses();
+        if let SudokuState::HaveGuesses((i, j)) = board.state {
+            assert_eq!(board.branch_count(), board.get_guesses(i, j).len());
+            assert!(board.branch_count() > 1);
+        } else {
+            unreachable!();
+        }
+    }
+
+    #[test]
+    fn completed_digits_works() {
+        let text = "926817345
+851394726
+473265891
+685123479
+734589162
+219746538
+5  472 1
+342951687
+197638254";
+        let board = Sudoku::from_text(text).unwrap();
+        assert_eq!(board.completed_digits(), vec![1, 2, 4, 5, 7]);
+        let empty = Sudoku::from_text("").unwrap();
+        assert!(empty.completed_digits().is_empty());
+    }
 }
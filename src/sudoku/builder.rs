@@ -0,0 +1,69 @@
+use super::{Item, Sudoku, BOX, N};
+use crate::prelude::*;
+
+/// Builds a board cell by cell, checking the values and the board when calling `build`
+///
+/// ```
+/// # use sudoku_solver::{Item, Sudoku};
+/// let board = Sudoku::builder().set(0, 0, 5).set(4, 2, 9).build().unwrap();
+/// assert_eq!(*board.get(0, 0), Item::Number(5));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SudokuBuilder {
+    cells: Vec<(usize, usize, u16)>,
+}
+
+impl SudokuBuilder {
+    /// Places `value` at (i, j), replacing any earlier value for that cell
+    pub fn set(mut self, i: usize, j: usize, value: u16) -> Self {
+        self.cells.push((i, j, value));
+        self
+    }
+
+    pub fn build(self) -> Result<Sudoku, String> {
+        let mut rows = Sudoku::empty_rows();
+        for (i, j, value) in self.cells {
+            Sudoku::<N>::check_cell(i, j)?;
+            if !(1..=N as u16).contains(&value) {
+                return Err(format!("Invalid value: {value}"));
+            }
+            rows[i][j] = Item::Number(value);
+        }
+        Sudoku::from_rows(rows, BOX)
+    }
+}
+
+impl Sudoku {
+    /// Empty [`SudokuBuilder`]
+    pub fn builder() -> SudokuBuilder {
+        SudokuBuilder::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_works() {
+        let board = Sudoku::builder()
+            .set(0, 2, 3)
+            .set(0, 4, 2)
+            .set(1, 0, 9)
+            .set(8, 6, 3)
+            .build()
+            .unwrap();
+        assert_eq!(*board.get(0, 2), Item::Number(3));
+        assert_eq!(*board.get(1, 0), Item::Number(9));
+        assert_eq!(*board.get(8, 6), Item::Number(3));
+        assert_eq!(*board.get(0, 0), Item::Empty);
+        assert_eq!(
+            board,
+            Sudoku::from_text("  3 2\n9\n\n\n\n\n\n\n      3").unwrap()
+        );
+
+        assert!(Sudoku::builder().set(9, 0, 1).build().is_err());
+        assert!(Sudoku::builder().set(0, 0, 10).build().is_err());
+        assert!(Sudoku::builder().set(0, 0, 1).set(0, 8, 1).build().is_err());
+    }
+}
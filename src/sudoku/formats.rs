@@ -0,0 +1,655 @@
+use super::{Item, Sudoku, BOX, N};
+use crate::prelude::*;
+use itertools::Itertools;
+
+/// URL-safe base64 alphabet (RFC 4648 section 5)
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+impl Sudoku {
+    /// Row-major cell values, 0 for cells without a number
+    pub fn to_flat(&self) -> Vec<u8> {
+        self.rows
+            .iter()
+            .flatten()
+            .map(|x| x.get_number().unwrap_or(0) as u8)
+            .collect()
+    }
+
+    /// Inverse of `to_flat`
+    pub fn from_flat(data: &[u8]) -> Result<Self, String> {
+        if data.len() != N * N {
+            return Err(format!(
+                "Invalid length: expected {} values, got {}",
+                N * N,
+                data.len()
+            ));
+        }
+        let mut rows = Self::empty_rows();
+        for (k, &x) in data.iter().enumerate() {
+            rows[k / N][k % N] = match x {
+                0 => Item::Empty,
+                1..=9 => Item::Number(x as u16),
+                x => return Err(format!("Invalid value: {x}")),
+            };
+        }
+        Self::from_rows(rows, BOX)
+    }
+
+    /// Parses the 81 digit string used by SudokuWiki and similar tools, with `0` as empty.
+    /// Any other character (like whitespace) is skipped and only the first 81 digits are read.
+    pub fn from_sudokuwiki(s: &str) -> Result<Self, String> {
+        let data = s
+            .chars()
+            .filter_map(|c| c.to_digit(10))
+            .take(N * N)
+            .map(|x| x as u8)
+            .collect::<Vec<_>>();
+        Self::from_flat(&data)
+    }
+
+    /// Parses 81 cells listed box by box (boxes left to right, top to bottom, each one read
+    /// row-major), with `0` or `.` as empty. Whitespace is skipped.
+    pub fn from_box_major(s: &str) -> Result<Self, String> {
+        let cells = s.chars().filter(|c| !c.is_whitespace()).collect_vec();
+        if cells.len() != N * N {
+            return Err(format!(
+                "Invalid length: expected {} cells, got {}",
+                N * N,
+                cells.len()
+            ));
+        }
+        let mut rows = Self::empty_rows();
+        for (b, chunk) in cells.chunks(N).enumerate() {
+            for (&(i, j), &c) in BOX.cells(b).iter().zip(chunk) {
+                rows[i][j] = match c {
+                    '0' | '.' => Item::Empty,
+                    '1'..='9' => Item::Number(c.to_digit(10).unwrap() as u16),
+                    c => return Err(format!("Invalid char: {c}")),
+                };
+            }
+        }
+        Self::from_rows(rows, BOX)
+    }
+
+    /// Parses one row per line with cells split by `sep` (like CSV or TSV spreadsheet exports).
+    /// Empty fields are empty cells, surrounding whitespace is ignored and blank lines skipped.
+    pub fn from_delimited(text: &str, sep: char) -> Result<Self, String> {
+        let lines = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                line.split(sep)
+                    .map(|field| match field.trim() {
+                        "" => Ok(0),
+                        field => field
+                            .parse::<u8>()
+                            .map_err(|_| format!("Invalid field: {field:?}")),
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if lines.len() != N || lines.iter().any(|cells| cells.len() != N) {
+            return Err(format!("Invalid grid: expected {N} rows of {N} cells"));
+        }
+        Self::from_flat(&lines.concat())
+    }
+
+    /// Grid in the format used by the Simple Sudoku program (.ss files)
+    pub fn to_simple_sudoku(&self) -> String {
+        let border = "*-----------*";
+        let mut s = format!("{border}\n");
+        for (i, row) in self.rows.iter().enumerate() {
+            if i > 0 && i % 3 == 0 {
+                s.push_str("|---+---+---|\n");
+            }
+            for (j, x) in row.iter().enumerate() {
+                if j % 3 == 0 {
+                    s.push('|');
+                }
+                match x.get_number() {
+                    Some(n) => s.push_str(&n.to_string()),
+                    None => s.push('.'),
+                }
+            }
+            s.push_str("|\n");
+        }
+        s.push_str(border);
+        s.push('\n');
+        s
+    }
+
+    /// Parses the Simple Sudoku (.ss) grid format, separator lines and characters are skipped
+    pub fn from_simple_sudoku(s: &str) -> Result<Self, String> {
+        let lines = s
+            .lines()
+            .map(|line| {
+                line.chars()
+                    .filter(|&c| c == '.' || c.is_ascii_digit())
+                    .map(|c| c.to_digit(10).unwrap_or(0) as u8)
+                    .collect::<Vec<_>>()
+            })
+            .filter(|cells| !cells.is_empty())
+            .collect::<Vec<_>>();
+        if lines.len() != N || lines.iter().any(|cells| cells.len() != N) {
+            return Err(format!("Invalid grid: expected {N} rows of {N} cells"));
+        }
+        Self::from_flat(&lines.concat())
+    }
+
+    /// Pencilmark text with each cell as a 3x3 block of its candidates, digit `d` at row
+    /// `(d - 1) / 3` and column `(d - 1) % 3` of the block, missing ones blank. Placed numbers
+    /// are a single digit in the middle of the block (like a lone candidate 5). Cells are split
+    /// by `:` and `+`, boxes by `|` and `-`.
+    pub fn to_pencilmark_text(&self) -> String {
+        let box_border = format!("+{}\n", "-----------+".repeat(3));
+        let cell_border = format!("|{}\n", "---+---+---|".repeat(3));
+        let mut s = box_border.clone();
+        for i in 0..N {
+            for r in 0..3 {
+                s.push('|');
+                for j in 0..N {
+                    s.push_str(&self.candidate_block_row(i, j, r, ' ', |n| format!(" {n} ")));
+                    s.push(if j % 3 == 2 { '|' } else { ':' });
+                }
+                s.push('\n');
+            }
+            s.push_str(if i % 3 == 2 {
+                &box_border
+            } else {
+                &cell_border
+            });
+        }
+        s
+    }
+
+    /// Markdown table (with an empty header row), boxes are told apart by writing the
+    /// numbers of alternating boxes in bold
+    pub fn to_markdown(&self) -> String {
+        let mut s = format!("|{}\n|{}\n", "   |".repeat(N), ":-:|".repeat(N));
+        for (i, row) in self.rows.iter().enumerate() {
+            s.push('|');
+            for (j, x) in row.iter().enumerate() {
+                let bold = (i / 3 + j / 3) % 2 == 0;
+                match x.get_number() {
+                    Some(n) if bold => s.push_str(&format!(" **{n}** |")),
+                    Some(n) => s.push_str(&format!(" {n} |")),
+                    None => s.push_str("   |"),
+                }
+            }
+            s.push('\n');
+        }
+        s
+    }
+
+    /// Pencil-mark grid where each cell is a 3x3 block with its candidates in keypad order.
+    /// Placed numbers are shown as `(n)` in the middle of their block.
+    pub fn to_candidate_grid_string(&self) -> String {
+        let border = format!("+{}\n", "-------------+".repeat(3));
+        let mut s = border.clone();
+        for i in 0..N {
+            if i % 3 != 0 {
+                s.push_str(&format!("|{}\n", "             |".repeat(3)));
+            }
+            for r in 0..3 {
+                for j in 0..N {
+                    s.push_str(if j % 3 == 0 { "| " } else { " " });
+                    s.push_str(&self.candidate_block_row(i, j, r, '.', |n| format!("({n})")));
+                    if j % 3 == 2 {
+                        s.push(' ');
+                    }
+                }
+                s.push_str("|\n");
+            }
+            if i % 3 == 2 {
+                s.push_str(&border);
+            }
+        }
+        s
+    }
+
+    /// Row `r` (0 to 2) of the 3x3 pencil-mark block of (i, j): the candidates `3r + 1` to
+    /// `3r + 3` with `missing` in place of the absent ones. Cells with a number are blank but
+    /// for the middle row, which is `given` of the number.
+    fn candidate_block_row(
+        &self,
+        i: usize,
+        j: usize,
+        r: usize,
+        missing: char,
+        given: impl Fn(u16) -> String,
+    ) -> String {
+        match self.get(i, j).get_number() {
+            Some(n) if r == 1 => given(n),
+            Some(_) => "   ".to_string(),
+            None => {
+                let guesses = self.get_guesses(i, j);
+                (1..=3)
+                    .map(|k| r as u16 * 3 + k)
+                    .map(|x| match guesses.contains(&x) {
+                        true => char::from_digit(x as u32, 10).unwrap(),
+                        false => missing,
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// CNF encoding in DIMACS format for SAT solvers, with variable `81 * i + 9 * j + d` true
+    /// when cell (i, j) holds digit `d`. Uses the extended encoding (every cell and unit holds
+    /// each digit at least and at most once) plus one unit clause per clue.
+    pub fn to_dimacs(&self) -> String {
+        let var = |(i, j): (usize, usize), d: usize| (N * N * i + N * j + d) as i32;
+        let mut clauses: Vec<Vec<i32>> = Vec::new();
+        for (i, j) in (0..N).cartesian_product(0..N) {
+            clauses.push((1..=N).map(|d| var((i, j), d)).collect());
+            for d1 in 1..=N {
+                for d2 in d1 + 1..=N {
+                    clauses.push(vec![-var((i, j), d1), -var((i, j), d2)]);
+                }
+            }
+        }
+        for unit in self.units() {
+            for d in 1..=N {
+                clauses.push(unit.iter().map(|&cell| var(cell, d)).collect());
+                for (k, &a) in unit.iter().enumerate() {
+                    for &b in &unit[k + 1..] {
+                        clauses.push(vec![-var(a, d), -var(b, d)]);
+                    }
+                }
+            }
+        }
+        for (i, j) in (0..N).cartesian_product(0..N) {
+            if let Some(x) = self.get(i, j).get_number() {
+                clauses.push(vec![var((i, j), x as usize)]);
+            }
+        }
+
+        let mut s = format!("p cnf {} {}\n", N * N * N, clauses.len());
+        for clause in clauses {
+            for literal in clause {
+                s.push_str(&format!("{literal} "));
+            }
+            s.push_str("0\n");
+        }
+        s
+    }
+
+    /// Reads the solved grid from the model (signed literals) a SAT solver found for
+    /// `to_dimacs`. Every cell needs exactly one true variable and the clues must be kept.
+    pub fn from_dimacs_model(&self, model: &[i32]) -> Result<Sudoku, String> {
+        let mut data = vec![0; N * N];
+        for &literal in model.iter().filter(|&&x| x > 0) {
+            let var = literal as usize - 1;
+            if var >= N * N * N {
+                return Err(format!("Invalid variable: {literal}"));
+            }
+            let (cell, digit) = (var / N, (var % N + 1) as u8);
+            if data[cell] != 0 {
+                return Err(format!(
+                    "Cell ({},{}) is assigned more than one digit",
+                    cell / N,
+                    cell % N
+                ));
+            }
+            data[cell] = digit;
+        }
+        if let Some(cell) = data.iter().position(|&x| x == 0) {
+            return Err(format!("Cell ({},{}) has no digit", cell / N, cell % N));
+        }
+        for (cell, (&x, given)) in data.iter().zip(self.to_flat()).enumerate() {
+            if given != 0 && given != x {
+                return Err(format!(
+                    "Cell ({},{}) should keep its clue {given}",
+                    cell / N,
+                    cell % N
+                ));
+            }
+        }
+        Self::from_flat(&data)
+    }
+
+    /// Compact URL-safe encoding: two cells per byte (4 bits each), written as unpadded
+    /// base64, 55 characters for a 9x9 board
+    pub fn to_base64(&self) -> String {
+        let bytes = self
+            .to_flat()
+            .chunks(2)
+            .map(|pair| pair[0] << 4 | pair.get(1).copied().unwrap_or(0))
+            .collect::<Vec<_>>();
+        let mut s = String::new();
+        for chunk in bytes.chunks(3) {
+            let bits = chunk
+                .iter()
+                .enumerate()
+                .fold(0u32, |acc, (k, &b)| acc | (b as u32) << (16 - 8 * k));
+            for k in 0..=chunk.len() {
+                s.push(BASE64[(bits >> (18 - 6 * k) & 0x3f) as usize] as char);
+            }
+        }
+        s
+    }
+
+    /// Inverse of `to_base64`
+    pub fn from_base64(s: &str) -> Result<Self, String> {
+        let values = s
+            .chars()
+            .map(|c| {
+                BASE64
+                    .iter()
+                    .position(|&b| b as char == c)
+                    .map(|x| x as u32)
+                    .ok_or_else(|| format!("Invalid base64 character: {c:?}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut bytes = Vec::new();
+        for chunk in values.chunks(4) {
+            if chunk.len() < 2 {
+                return Err("Invalid base64 length".to_string());
+            }
+            let bits = chunk
+                .iter()
+                .enumerate()
+                .fold(0u32, |acc, (k, &x)| acc | x << (18 - 6 * k));
+            for k in 0..chunk.len() - 1 {
+                bytes.push((bits >> (16 - 8 * k)) as u8);
+            }
+        }
+        if bytes.len() != (N * N).div_ceil(2) {
+            return Err(format!(
+                "Invalid length: expected {} bytes, got {}",
+                (N * N).div_ceil(2),
+                bytes.len()
+            ));
+        }
+        let data = bytes
+            .iter()
+            .flat_map(|b| [b >> 4, b & 0x0f])
+            .take(N * N)
+            .collect::<Vec<_>>();
+        Self::from_flat(&data)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flat_round_trip_works() {
+        let text = " 1
+69  2  57
+    692
+  9   4
+47     2
+581 9   3
+  5  86
+ 4 2  8 1
+   6   4";
+        let board = Sudoku::from_text(text).unwrap();
+        let flat = board.to_flat();
+        assert_eq!(flat.len(), 81);
+        assert_eq!(&flat[..9], &[0, 1, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(Sudoku::from_flat(&flat).unwrap(), board);
+    }
+
+    #[test]
+    fn from_flat_fails_on_invalid_input() {
+        let err = Sudoku::from_flat(&[0; 80]).unwrap_err();
+        println!("{err}");
+        let mut data = [0; 81];
+        data[3] = 10;
+        let err = Sudoku::from_flat(&data).unwrap_err();
+        println!("{err}");
+    }
+
+    #[test]
+    fn from_sudokuwiki_works() {
+        let s = "003020600900305001001806400
+008102900700000008006708200
+002609500800203009005010300 ";
+        let board = Sudoku::from_sudokuwiki(s).unwrap();
+        println!("{board}");
+        let clues = board
+            .to_flat()
+            .iter()
+            .enumerate()
+            .filter(|(_, &x)| x != 0)
+            .map(|(k, &x)| (k / 9, k % 9, x))
+            .take(4)
+            .collect::<Vec<_>>();
+        assert_eq!(clues, vec![(0, 2, 3), (0, 4, 2), (0, 6, 6), (1, 0, 9)]);
+        assert_eq!(*board.get(8, 6), Item::Number(3));
+    }
+
+    #[test]
+    fn from_box_major_works() {
+        let s = "003900001 020305806 600001400
+008700006 102000708 900008200
+002800005 609203010 500009300";
+        let board = Sudoku::from_box_major(s).unwrap();
+        let row_major = Sudoku::from_sudokuwiki(
+            "003020600900305001001806400008102900700000008006708200002609500800203009005010300",
+        )
+        .unwrap();
+        println!("{board}");
+        assert_eq!(board, row_major);
+        assert_eq!(
+            Sudoku::from_box_major(&s.replace('0', ".")).unwrap(),
+            row_major
+        );
+        assert!(Sudoku::from_box_major("123").is_err());
+    }
+
+    #[test]
+    fn simple_sudoku_round_trip_works() {
+        let text = " 1
+69  2  57
+    692
+  9   4
+47     2
+581 9   3
+  5  86
+ 4 2  8 1
+   6   4";
+        let board = Sudoku::from_text(text).unwrap();
+        let ss = board.to_simple_sudoku();
+        println!("{ss}");
+        assert_eq!(ss.lines().count(), 13);
+        assert_eq!(ss.lines().nth(1).unwrap(), "|.1.|...|...|");
+        assert_eq!(ss.lines().nth(4).unwrap(), "|---+---+---|");
+        assert_eq!(Sudoku::from_simple_sudoku(&ss).unwrap(), board);
+        assert!(Sudoku::from_simple_sudoku("|...|").is_err());
+    }
+
+    #[test]
+    fn base64_round_trip_works() {
+        let text = " 1
+69  2  57
+    692
+  9   4
+47     2
+581 9   3
+  5  86
+ 4 2  8 1
+   6   4";
+        let board = Sudoku::from_text(text).unwrap();
+        let encoded = board.to_base64();
+        println!("{encoded}");
+        assert_eq!(encoded.len(), 55);
+        assert!(encoded
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert!(encoded.starts_with("AQ"));
+        assert_eq!(Sudoku::from_base64(&encoded).unwrap(), board);
+        assert!(Sudoku::from_base64("AQ").is_err());
+        assert!(Sudoku::from_base64(&encoded.replace('A', "+")).is_err());
+    }
+
+    #[test]
+    fn candidate_grid_works() {
+        let text = "926817 45
+8 139 726
+4  26 891
+6 5   47
+73  8 1 2
+2 97465 8
+    72
+ 42  1  7
+1 76 8  4";
+        let mut board = Sudoku::from_text(text).unwrap();
+        board.compute_guesses();
+        let grid = board.to_candidate_grid_string();
+        println!("{grid}");
+        let lines = grid.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 1 + 9 * 3 + 6 + 3);
+        assert!(lines.iter().all(|line| line.chars().count() == 43));
+        assert_eq!(lines[0], "+-------------+-------------+-------------+");
+        assert_eq!(lines[2], "| (9) (2) (6) | (8) (1) (7) | (3) (4) (5) |");
+        // Row 6 column 8 has candidates 3 and 9
+        assert_eq!(&lines[25][38..41], "..3");
+        assert_eq!(&lines[27][38..41], "..9");
+    }
+
+    #[test]
+    fn pencilmark_text_works() {
+        let text = "926817 45
+8 139 726
+4  26 891
+6 5   47
+73  8 1 2
+2 97465 8
+    72
+ 42  1  7
+1 76 8  4";
+        let board = Sudoku::from_text(text).unwrap();
+        let pencilmarks = board.to_pencilmark_text();
+        println!("{pencilmarks}");
+        let lines = pencilmarks.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 1 + 9 * 4);
+        assert!(lines.iter().all(|line| line.chars().count() == 37));
+        assert_eq!(lines[0], "+-----------+-----------+-----------+");
+        assert_eq!(lines[2], "| 9 : 2 : 6 | 8 : 1 : 7 |   : 4 : 5 |");
+        assert_eq!(lines[4], "|---+---+---|---+---+---|---+---+---|");
+        // Cell (0, 6) only has 3, top right of its block
+        assert_eq!(&lines[1][25..28], "  3");
+        assert_eq!(&lines[2][25..28], "   ");
+        // Cell (6, 8) has 3 and 9
+        assert_eq!(&lines[25][33..36], "  3");
+        assert_eq!(&lines[26][33..36], "   ");
+        assert_eq!(&lines[27][33..36], "  9");
+    }
+
+    #[test]
+    fn from_delimited_works() {
+        let csv = ",,3,,2,,6,,
+9,,,3,,5,,,1
+,,1,8,,6,4,,
+,,8,1,,2,9,,
+7,,,,,,,,8
+,,6,7,,8,2,,
+,,2,6,,9,5,,
+8,,,2,,3,,,9
+,,5,,1,,3,,
+";
+        let board = Sudoku::from_delimited(csv, ',').unwrap();
+        let text = "  3 2 6
+9  3 5  1
+  18 64
+  81 29
+7       8
+  67 82
+  26 95
+8  2 3  9
+  5 1 3";
+        assert_eq!(board, Sudoku::from_text(text).unwrap());
+        let tsv = csv.replace(',', "\t");
+        assert_eq!(Sudoku::from_delimited(&tsv, '\t').unwrap(), board);
+        assert!(Sudoku::from_delimited(&csv.replace(",,3", ",x,3"), ',').is_err());
+        assert!(Sudoku::from_delimited(",,3", ',').is_err());
+    }
+
+    #[test]
+    fn to_dimacs_works() {
+        let empty = Sudoku::from_text("").unwrap().to_dimacs();
+        // Cell definedness and uniqueness: 81 * (1 + 36), units: 27 * 9 * (1 + 36)
+        let base = 81 * 37 + 27 * 9 * 37;
+        assert_eq!(empty.lines().next().unwrap(), format!("p cnf 729 {base}"));
+        assert_eq!(empty.lines().count(), 1 + base);
+        assert!(empty.lines().skip(1).all(|line| line.ends_with(" 0")));
+
+        let text = "  3 2 6
+9  3 5  1
+  18 64
+  81 29
+7       8
+  67 82
+  26 95
+8  2 3  9
+  5 1 3";
+        let board = Sudoku::from_text(text).unwrap();
+        let cnf = board.to_dimacs();
+        assert_eq!(cnf.lines().count(), 1 + base + board.clue_count());
+        // (0, 2) = 3 is variable 9 * 2 + 3, (8, 6) = 3 is 81 * 8 + 9 * 6 + 3
+        assert_eq!(cnf.lines().nth(base + 1).unwrap(), "21 0");
+        assert_eq!(cnf.lines().last().unwrap(), "705 0");
+    }
+
+    #[test]
+    fn from_dimacs_model_works() {
+        let text = "  3 2 6
+9  3 5  1
+  18 64
+  81 29
+7       8
+  67 82
+  26 95
+8  2 3  9
+  5 1 3";
+        let board = Sudoku::from_text(text).unwrap();
+        let solution = board.solved().unwrap();
+        let cnf = board.to_dimacs();
+        // Model a SAT solver would give: every variable with its sign
+        let model = (1..=729)
+            .map(|var| {
+                let (cell, digit) = ((var - 1) / 9, (var - 1) % 9 + 1);
+                match solution.to_flat()[cell as usize] as i32 == digit {
+                    true => var,
+                    false => -var,
+                }
+            })
+            .collect::<Vec<_>>();
+        // The model satisfies every clause of the encoding
+        assert!(cnf.lines().skip(1).all(|line| line
+            .split_whitespace()
+            .map(|x| x.parse::<i32>().unwrap())
+            .any(|x| x != 0 && model.contains(&x))));
+        let decoded = board.from_dimacs_model(&model).unwrap();
+        assert!(decoded.solution_eq(&solution));
+
+        // (0, 2) holds 1 instead of its clue 3
+        let mut wrong = model.clone();
+        wrong[18] = 19;
+        wrong[20] = -21;
+        assert!(board.from_dimacs_model(&wrong).is_err());
+        assert!(board.from_dimacs_model(&model[..700]).is_err());
+    }
+
+    #[test]
+    fn to_markdown_works() {
+        let text = "  3 2 6
+9  3 5  1
+  18 64
+  81 29
+7       8
+  67 82
+  26 95
+8  2 3  9
+  5 1 3";
+        let markdown = Sudoku::from_text(text).unwrap().to_markdown();
+        println!("{markdown}");
+        let lines = markdown.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2 + 9);
+        assert!(lines.iter().all(|line| line.matches('|').count() == 10));
+        assert_eq!(lines[2], "|   |   | **3** |   | 2 |   | **6** |   |   |");
+    }
+}
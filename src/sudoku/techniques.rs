@@ -0,0 +1,908 @@
+use super::{digit_bit, mask_digits, unique, Item, Sudoku, SudokuState, UnitKind};
+use crate::prelude::*;
+use alloc::collections::BTreeMap;
+use itertools::Itertools;
+
+/// Logic techniques, ordered from simplest to hardest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Technique {
+    NakedSingle,
+    HiddenSingle,
+    NakedPair,
+    NakedTriple,
+    XWing,
+    Swordfish,
+}
+
+/// A candidate removed from a cell by a logic technique
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Elimination {
+    pub cell: (usize, usize),
+    pub digit: u16,
+    pub technique: Technique,
+}
+
+/// A unit (kind and index) and the only two cells of it where a digit can go, see
+/// `Sudoku::conjugate_pairs`
+pub type ConjugatePair = (UnitKind, usize, (usize, usize), (usize, usize));
+
+/// An X-Wing: the only places for `digit` in two rows are the same two columns (or the other
+/// way around), so `digit` can be removed from the rest of the lines crossing the pattern
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct XWing {
+    pub digit: u16,
+    pub rows: [usize; 2],
+    pub cols: [usize; 2],
+}
+
+/// A number placed by a single, with the unit where it was the last place for hidden singles
+struct Placement {
+    cell: (usize, usize),
+    digit: u16,
+    unit: Option<(UnitKind, usize)>,
+}
+
+/// Cells of a unit that together hold exactly as many candidates as there are cells
+struct NakedSubset {
+    cells: Vec<(usize, usize)>,
+    digits: Vec<u16>,
+    unit: Vec<(usize, usize)>,
+}
+
+/// `digit` is confined to the `cover` lines within the `base` lines, so it can be removed
+/// from the rest of the `cover` lines. Lines are rows when `rows` is true, columns otherwise.
+struct Fish {
+    digit: u16,
+    rows: bool,
+    base: Vec<usize>,
+    cover: Vec<usize>,
+}
+
+impl<const N: usize> Sudoku<N> {
+    /// Applies logic techniques until they make no more progress, then refreshes the guesses
+    /// and state like `compute_guesses`. Eliminated candidates are kept on the board (apart
+    /// from the ones excluded with `exclude_candidate`) and returned in the order they were
+    /// removed.
+    pub fn propagate(&mut self) -> Vec<Elimination> {
+        self.propagate_with(Technique::Swordfish)
+    }
+
+    /// Like `propagate`, but only uses techniques up to `max`
+    pub fn propagate_with(&mut self, max: Technique) -> Vec<Elimination> {
+        let mut log = Vec::new();
+        self.propagate_placing(max, &mut log, &mut Vec::new());
+        log
+    }
+
+    /// `propagate_with`, also recording every number placed by a single in `placements`
+    fn propagate_placing(
+        &mut self,
+        max: Technique,
+        log: &mut Vec<Elimination>,
+        placements: &mut Vec<Placement>,
+    ) {
+        loop {
+            let filled = self.update_guesses(&mut Vec::new());
+            let progressed = !filled.is_empty();
+            placements.extend(filled.into_iter().map(|(i, j, digit)| Placement {
+                cell: (i, j),
+                digit,
+                unit: None,
+            }));
+            if !matches!(self.state, SudokuState::HaveGuesses(_)) {
+                break;
+            }
+            if progressed {
+                continue;
+            }
+            if max >= Technique::HiddenSingle && self.fill_hidden_singles(placements) > 0 {
+                continue;
+            }
+            if max >= Technique::NakedPair
+                && self.eliminate_naked_subsets(2, Technique::NakedPair, log) > 0
+            {
+                continue;
+            }
+            if max >= Technique::NakedTriple
+                && self.eliminate_naked_subsets(3, Technique::NakedTriple, log) > 0
+            {
+                continue;
+            }
+            if max >= Technique::XWing && self.eliminate_fish(2, Technique::XWing, log) > 0 {
+                continue;
+            }
+            // X-Wings are exhausted first, so what a swordfish removes needs its three lines
+            if max >= Technique::Swordfish && self.eliminate_fish(3, Technique::Swordfish, log) > 0
+            {
+                continue;
+            }
+            break;
+        }
+    }
+
+    /// Whether naked and hidden singles alone solve the board
+    pub fn is_basic(&self) -> bool {
+        let mut board = self.clone();
+        board.propagate_with(Technique::HiddenSingle);
+        board.is_solved()
+    }
+
+    /// Places every hidden single of every unit, adding them to `placements`. Returns how
+    /// many were placed.
+    fn fill_hidden_singles(&mut self, placements: &mut Vec<Placement>) -> usize {
+        let mut placed = 0;
+        for (kind, index) in UnitKind::ALL.into_iter().cartesian_product(0..N) {
+            for (i, j, x) in self.hidden_singles(&self.unit_cells(kind, index)) {
+                // An earlier placement may have taken the cell or the digit
+                if self.get(i, j).get_number().is_none() && self.get_guesses(i, j).contains(&x) {
+                    self.set(i, j, x);
+                    placements.push(Placement {
+                        cell: (i, j),
+                        digit: x,
+                        unit: Some((kind, index)),
+                    });
+                    placed += 1;
+                }
+            }
+        }
+        placed
+    }
+
+    /// Performs one pass of naked single filling, returns the cells that were filled.
+    /// Other cells are left as they are.
+    pub fn step(&mut self) -> Vec<(usize, usize, u16)> {
+        self.fill_singles_pass(|_, _, _| {})
+    }
+
+    /// Only the single-filling part of `compute_guesses`: one pass placing every cell with a
+    /// single candidate, returns how many were filled. Guesses and state are left as they are.
+    pub fn fill_forced(&mut self) -> usize {
+        self.step().len()
+    }
+
+    /// Number of naked single placements that can be made one after the other from the current
+    /// board before none is left
+    pub fn single_chain_length(&self) -> usize {
+        let mut board = self.clone();
+        let mut length = 0;
+        loop {
+            match board.step().len() {
+                0 => return length,
+                filled => length += filled,
+            }
+        }
+    }
+
+    /// Average number of cells that naked singles fill after placing each candidate of (i, j),
+    /// a tie-break for picking a branching cell. Cells with a number give 0.
+    pub fn branch_cascade_estimate(&self, i: usize, j: usize) -> usize {
+        if self.get(i, j).get_number().is_some() {
+            return 0;
+        }
+        let guesses = self.get_guesses(i, j);
+        if guesses.is_empty() {
+            return 0;
+        }
+        let filled = guesses
+            .iter()
+            .map(|&x| {
+                let mut board = self.clone();
+                board.set(i, j, x);
+                board.single_chain_length()
+            })
+            .sum::<usize>();
+        filled / guesses.len()
+    }
+
+    /// For each cell filled in this solved board but empty in `original`, the single that
+    /// placed its number when solving `original` with logic techniques. Where logic runs out
+    /// the number of this board is guessed and solving goes on from there.
+    pub fn explain_solution(&self, original: &Self) -> Vec<((usize, usize), String)> {
+        let name = |kind: UnitKind| match kind {
+            UnitKind::Row => "row",
+            UnitKind::Column => "column",
+            UnitKind::Box => "box",
+        };
+        let mut board = original.snapshot();
+        let mut placements = Vec::new();
+        let mut guessed = Vec::new();
+        loop {
+            board.propagate_placing(Technique::Swordfish, &mut Vec::new(), &mut placements);
+            let SudokuState::HaveGuesses((i, j)) = board.state else {
+                break;
+            };
+            let Some(x) = self.get(i, j).get_number() else {
+                break;
+            };
+            board.set(i, j, x);
+            guessed.push((i, j));
+        }
+        let reasons = placements
+            .into_iter()
+            .map(|Placement { cell, digit, unit }| {
+                let reason = match unit {
+                    Some((kind, index)) => {
+                        format!("last remaining place for {digit} in {} {index}", name(kind))
+                    }
+                    None => format!("{digit} is the only candidate left for the cell"),
+                };
+                (cell, (digit, reason))
+            })
+            .collect::<BTreeMap<_, _>>();
+        (0..N)
+            .cartesian_product(0..N)
+            .filter(|&(i, j)| original.get(i, j).get_number().is_none())
+            .filter_map(|(i, j)| {
+                let x = self.get(i, j).get_number()?;
+                let reason = match reasons.get(&(i, j)) {
+                    Some((digit, reason)) if *digit == x => reason.clone(),
+                    _ if guessed.contains(&(i, j)) => {
+                        format!("{x} was guessed, logic techniques can't place a number here")
+                    }
+                    _ => return None,
+                };
+                Some(((i, j), reason))
+            })
+            .collect()
+    }
+
+    /// Groups of three cells in a unit that together hold exactly three candidates
+    pub fn find_naked_triples(&self) -> Vec<Vec<(usize, usize)>> {
+        unique(self.naked_subsets(3).into_iter().map(|subset| subset.cells))
+    }
+
+    /// Finds `size` cells in a unit whose candidates are exactly `size` digits
+    fn naked_subsets(&self, size: usize) -> Vec<NakedSubset> {
+        let mut subsets = Vec::new();
+        for unit in self.units() {
+            let candidates = unit
+                .iter()
+                .filter(|&&(i, j)| !matches!(self.get(i, j), Item::Number(_)))
+                .map(|&(i, j)| ((i, j), self.candidate_mask(i, j)))
+                .filter(|(_, mask)| (2..=size).contains(&(mask.count_ones() as usize)))
+                .collect_vec();
+            for combination in candidates.iter().combinations(size) {
+                let digits =
+                    mask_digits(combination.iter().fold(0, |mask, (_, m)| mask | m)).collect_vec();
+                if digits.len() == size {
+                    let cells = combination.iter().map(|&&(cell, _)| cell).collect();
+                    subsets.push(NakedSubset {
+                        cells,
+                        digits,
+                        unit: unit.clone(),
+                    });
+                }
+            }
+        }
+        subsets
+    }
+
+    /// Removes the digits of each naked subset from the rest of its unit,
+    /// returns the number of eliminated candidates
+    fn eliminate_naked_subsets(
+        &mut self,
+        size: usize,
+        technique: Technique,
+        log: &mut Vec<Elimination>,
+    ) -> usize {
+        let mut eliminated = 0;
+        for NakedSubset {
+            cells,
+            digits,
+            unit,
+        } in self.naked_subsets(size)
+        {
+            for (i, j) in unit {
+                if cells.contains(&(i, j)) || matches!(self.get(i, j), Item::Number(_)) {
+                    continue;
+                }
+                let mask = digits.iter().fold(0, |mask, &x| mask | digit_bit(x));
+                for x in mask_digits(self.candidate_mask(i, j) & mask) {
+                    self.eliminate_candidate(i, j, x);
+                    log.push(Elimination {
+                        cell: (i, j),
+                        digit: x,
+                        technique,
+                    });
+                    eliminated += 1;
+                }
+            }
+        }
+        eliminated
+    }
+
+    /// Units where `digit` has exactly two candidate cells (a strong link: if one of them
+    /// doesn't hold `digit` the other one does)
+    pub fn conjugate_pairs(&self, digit: u16) -> Vec<ConjugatePair> {
+        UnitKind::ALL
+            .into_iter()
+            .cartesian_product(0..N)
+            .filter_map(|(kind, index)| {
+                let places = self
+                    .unit_cells(kind, index)
+                    .into_iter()
+                    .filter(|&(i, j)| {
+                        self.get(i, j).get_number().is_none()
+                            && self.get_guesses(i, j).contains(&digit)
+                    })
+                    .collect_vec();
+                match places[..] {
+                    [a, b] => Some((kind, index, a, b)),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    /// X-Wings that would eliminate at least one candidate, without applying them
+    pub fn find_x_wings(&self) -> Vec<XWing> {
+        let x_wings = self
+            .fish(2)
+            .into_iter()
+            .filter(|fish| {
+                (0..N)
+                    .cartesian_product(fish.cover.iter().copied())
+                    .any(|(line, k)| {
+                        let (i, j) = if fish.rows { (line, k) } else { (k, line) };
+                        !fish.base.contains(&line)
+                            && self.get(i, j).get_number().is_none()
+                            && self.get_guesses(i, j).contains(&fish.digit)
+                    })
+            })
+            .map(|fish| {
+                let base = [fish.base[0], fish.base[1]];
+                let cover = [fish.cover[0], fish.cover[1]];
+                match fish.rows {
+                    true => XWing {
+                        digit: fish.digit,
+                        rows: base,
+                        cols: cover,
+                    },
+                    false => XWing {
+                        digit: fish.digit,
+                        rows: cover,
+                        cols: base,
+                    },
+                }
+            });
+        unique(x_wings)
+    }
+
+    /// Finds fish patterns of the given size (2 is an X-Wing, 3 a swordfish)
+    fn fish(&self, size: usize) -> Vec<Fish> {
+        let mut found = Vec::new();
+        for (digit, rows) in (1..=N as u16).cartesian_product([true, false]) {
+            let cell = |line: usize, k: usize| if rows { (line, k) } else { (k, line) };
+            let lines = (0..N)
+                .map(|line| {
+                    let places = (0..N)
+                        .filter(|&k| {
+                            let (i, j) = cell(line, k);
+                            self.get(i, j).get_number().is_none()
+                                && self.get_guesses(i, j).contains(&digit)
+                        })
+                        .collect_vec();
+                    (line, places)
+                })
+                .filter(|(_, places)| (2..=size).contains(&places.len()))
+                .collect_vec();
+            for combination in lines.iter().combinations(size) {
+                let cover = combination
+                    .iter()
+                    .flat_map(|(_, places)| places)
+                    .copied()
+                    .sorted()
+                    .dedup()
+                    .collect_vec();
+                if cover.len() == size {
+                    let base = combination.iter().map(|(line, _)| *line).collect();
+                    found.push(Fish {
+                        digit,
+                        rows,
+                        base,
+                        cover,
+                    });
+                }
+            }
+        }
+        found
+    }
+
+    /// Removes each fish digit from its cover lines outside the base lines,
+    /// returns the number of eliminated candidates
+    fn eliminate_fish(
+        &mut self,
+        size: usize,
+        technique: Technique,
+        log: &mut Vec<Elimination>,
+    ) -> usize {
+        let mut eliminated = 0;
+        for fish in self.fish(size) {
+            for (line, k) in (0..N).cartesian_product(fish.cover.iter().copied()) {
+                let (i, j) = if fish.rows { (line, k) } else { (k, line) };
+                if fish.base.contains(&line)
+                    || self.get(i, j).get_number().is_some()
+                    || !self.get_guesses(i, j).contains(&fish.digit)
+                {
+                    continue;
+                }
+                self.eliminate_candidate(i, j, fish.digit);
+                log.push(Elimination {
+                    cell: (i, j),
+                    digit: fish.digit,
+                    technique,
+                });
+                eliminated += 1;
+            }
+        }
+        eliminated
+    }
+
+    /// Hidden singles of every row, column and box, a cell found by several units is only
+    /// listed once
+    pub fn all_hidden_singles(&self) -> Vec<(usize, usize, u16)> {
+        unique(
+            self.units()
+                .iter()
+                .flat_map(|unit| self.hidden_singles(unit)),
+        )
+    }
+
+    /// Units with a single empty cell, as `(kind, index, i, j, digit)` with the empty cell and
+    /// the digit missing from the unit. Units where the digit can't legally go there are left
+    /// out.
+    pub fn last_cell_placements(&self) -> Vec<(UnitKind, usize, usize, usize, u16)> {
+        UnitKind::ALL
+            .into_iter()
+            .cartesian_product(0..N)
+            .filter_map(|(kind, index)| {
+                let cells = self.unit_cells(kind, index);
+                let (empty, filled): (Vec<_>, Vec<_>) = cells
+                    .iter()
+                    .partition(|&&(i, j)| self.get(i, j).get_number().is_none());
+                let &[(i, j)] = &empty[..] else {
+                    return None;
+                };
+                let placed = filled
+                    .iter()
+                    .filter_map(|&(i, j)| self.get(i, j).get_number())
+                    .collect_vec();
+                let digit = (1..=N as u16).find(|x| !placed.contains(x))?;
+                self.get_guesses(i, j)
+                    .contains(&digit)
+                    .then_some((kind, index, i, j, digit))
+            })
+            .collect()
+    }
+
+    /// Cells where a digit can only be placed in that one cell of its box
+    pub fn box_hidden_singles(&self) -> Vec<(usize, usize, u16)> {
+        (0..N)
+            .flat_map(|b| self.hidden_singles(&self.box_cells(b)))
+            .collect()
+    }
+
+    /// Digits that have a single legal cell within the given unit
+    fn hidden_singles(&self, cells: &[(usize, usize)]) -> Vec<(usize, usize, u16)> {
+        let candidates = cells
+            .iter()
+            .filter(|&&(i, j)| !matches!(self.get(i, j), Item::Number(_)))
+            .map(|&(i, j)| ((i, j), self.get_guesses(i, j)))
+            .collect_vec();
+        (1..=N as u16)
+            .filter_map(|x| {
+                let mut places = candidates
+                    .iter()
+                    .filter(|(_, guesses)| guesses.contains(&x));
+                match (places.next(), places.next()) {
+                    (Some(&((i, j), _)), None) => Some((i, j, x)),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::sudoku::N;
+
+    #[test]
+    fn box_hidden_singles_works() {
+        let text = "
+    1
+       1
+
+ 1
+
+
+
+  1";
+        let board = Sudoku::from_text(text).unwrap();
+        println!("{board}");
+        let singles = board.box_hidden_singles();
+        assert_eq!(singles, vec![(0, 0, 1)]);
+    }
+
+    #[test]
+    fn last_cell_placements_works() {
+        let board = Sudoku::from_text(" 23456789").unwrap();
+        assert_eq!(
+            board.last_cell_placements(),
+            vec![(UnitKind::Row, 0, 0, 0, 1)]
+        );
+        let text = "926817345
+851394726
+473265891
+685123479
+734589162
+219746538
+568472913
+342951687
+19763825";
+        let board = Sudoku::from_text(text).unwrap();
+        assert_eq!(
+            board.last_cell_placements(),
+            vec![
+                (UnitKind::Row, 8, 8, 8, 4),
+                (UnitKind::Column, 8, 8, 8, 4),
+                (UnitKind::Box, 8, 8, 8, 4),
+            ]
+        );
+        let dead = Sudoku::from_text(" 23456789\n\n\n\n1").unwrap();
+        assert!(dead.last_cell_placements().is_empty());
+    }
+
+    #[test]
+    fn all_hidden_singles_works() {
+        let text = "      39
+6       7
+ 571
+     74 5
+  3 1
+58  927
+13
+ 46  5 3
+  8";
+        let board = Sudoku::from_text(text).unwrap();
+        let singles = board.all_hidden_singles();
+        println!("{singles:?}");
+        assert!(singles.len() > 1);
+        assert_eq!(singles.iter().unique().count(), singles.len());
+        for unit in board.units() {
+            for single in board.hidden_singles(&unit) {
+                assert!(singles.contains(&single));
+            }
+        }
+        let solution = board.solved().unwrap();
+        for &(i, j, x) in &singles {
+            assert_eq!(*solution.get(i, j), Item::Number(x));
+        }
+    }
+
+    #[test]
+    fn is_basic_works() {
+        let basic = "      39
+6       7
+ 571
+     74 5
+  3 1
+58  927
+13
+ 46  5 3
+  8";
+        let needs_pairs = "       9
+   8  1
+  7   2
+   3 7
+   51
+58149 76
+  5     2
+9 6    3
+ 2  3 54";
+        assert!(Sudoku::from_text(basic).unwrap().is_basic());
+        let board = Sudoku::from_text(needs_pairs).unwrap();
+        assert!(!board.is_basic());
+        let mut with_pairs = board.clone();
+        with_pairs.propagate_with(Technique::NakedPair);
+        assert!(with_pairs.is_solved());
+    }
+
+    #[test]
+    fn step_works() {
+        let text = "  3 2 6
+9  3 5  1
+  18 64
+  81 29
+7       8
+  67 82
+  26 95
+8  2 3  9
+  5 1 3";
+        let mut board = Sudoku::from_text(text).unwrap();
+        while board.filled_count() < N * N {
+            let filled = board.step();
+            println!("{filled:?}");
+            assert!(!filled.is_empty());
+            for (i, j, x) in filled {
+                assert_eq!(*board.get(i, j), Item::Number(x));
+            }
+        }
+        println!("{board}");
+    }
+
+    #[test]
+    fn fill_forced_works() {
+        let text = "926817 45
+8 139 726
+4  26 891
+6 5   47
+73  8 1 2
+2 97465 8
+    72
+ 42  1  7
+1 76 8  4";
+        let mut board = Sudoku::from_text(text).unwrap();
+        let before = board.filled_count();
+        let filled = board.fill_forced();
+        println!("{board}");
+        assert!(filled > 0);
+        assert_eq!(board.filled_count(), before + filled);
+        assert_eq!(*board.get(0, 6), Item::Number(3));
+        assert_eq!(*board.get(1, 1), Item::Number(5));
+        assert_eq!(board.state, SudokuState::Unknown);
+        // Same pass as the one `compute_guesses` makes before listing the guesses
+        let mut computed = Sudoku::from_text(text).unwrap();
+        computed.compute_guesses();
+        assert_eq!(computed.to_flat(), board.to_flat());
+    }
+
+    #[test]
+    fn single_chain_length_works() {
+        let easy = "  3 2 6
+9  3 5  1
+  18 64
+  81 29
+7       8
+  67 82
+  26 95
+8  2 3  9
+  5 1 3";
+        let hard = "1    7 9
+ 3  2   8
+  96  5
+  53  9
+ 1  8   2
+6    4
+3      1
+ 4      7
+  7   3";
+        let easy = Sudoku::from_text(easy).unwrap();
+        let hard = Sudoku::from_text(hard).unwrap();
+        let easy_chain = easy.single_chain_length();
+        let hard_chain = hard.single_chain_length();
+        println!("Easy: {easy_chain}, hard: {hard_chain}");
+        assert_eq!(easy_chain, N * N - easy.filled_count());
+        assert!(hard_chain < easy_chain);
+    }
+
+    #[test]
+    fn branch_cascade_estimate_works() {
+        let text = "       9
+   8  1
+  7   2
+   3 7
+   51
+58149 76
+  5     2
+9 6    3
+ 2  3 54";
+        let board = Sudoku::from_text(text).unwrap();
+        let constraining = board.branch_cascade_estimate(4, 1);
+        let loose = board.branch_cascade_estimate(0, 2);
+        println!("{constraining} {loose}");
+        assert!(constraining > loose);
+        assert_eq!(board.branch_cascade_estimate(0, 7), 0);
+    }
+
+    #[test]
+    fn explain_solution_works() {
+        let text = "      39
+6       7
+ 571
+     74 5
+  3 1
+58  927
+13
+ 46  5 3
+  8";
+        let original = Sudoku::from_text(text).unwrap();
+        let solution = original.solved().unwrap();
+        let explanations = solution.explain_solution(&original);
+        println!("{explanations:#?}");
+        assert_eq!(explanations.len(), N * N - original.clue_count());
+        for ((i, j), reason) in &explanations {
+            assert!(original.get(*i, *j).get_number().is_none());
+            assert!(!reason.is_empty());
+        }
+        // Both kinds of singles are needed for this board
+        let count = |prefix: &str| {
+            explanations
+                .iter()
+                .filter(|(_, reason)| reason.starts_with(prefix))
+                .count()
+        };
+        let hidden = count("last remaining place");
+        let naked = explanations
+            .iter()
+            .filter(|(_, reason)| reason.ends_with("is the only candidate left for the cell"))
+            .count();
+        println!("{hidden} hidden singles, {naked} naked singles");
+        assert!(hidden > 0 && naked > 0);
+        assert_eq!(hidden + naked, explanations.len());
+        assert!(solution.explain_solution(&solution).is_empty());
+
+        let hard = "1    7 9
+ 3  2   8
+  96  5
+  53  9
+ 1  8   2
+6    4
+3      1
+ 4      7
+  7   3";
+        let original = Sudoku::from_text(hard).unwrap();
+        let solution = original.solved().unwrap();
+        let explanations = solution.explain_solution(&original);
+        assert_eq!(explanations.len(), N * N - original.clue_count());
+        assert!(explanations
+            .iter()
+            .any(|(_, reason)| reason.ends_with("can't place a number here")));
+    }
+
+    #[test]
+    fn find_naked_triples_works() {
+        let text = "
+456
+789";
+        let mut board = Sudoku::from_text(text).unwrap();
+        let triples = board.find_naked_triples();
+        println!("{triples:?}");
+        assert!(triples.contains(&vec![(0, 0), (0, 1), (0, 2)]));
+        assert_eq!(board.get_guesses(0, 5), (1..=9).collect_vec());
+        board.propagate();
+        println!("{board}");
+        assert_eq!(board.get_guesses(0, 5), vec![4, 5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn propagate_logs_naked_pair_eliminations() {
+        let text = "  3
+456
+789";
+        let mut board = Sudoku::from_text(text).unwrap();
+        let log = board.propagate();
+        println!("{log:?}");
+        let expected = (3..N)
+            .cartesian_product([1, 2])
+            .map(|(j, digit)| Elimination {
+                cell: (0, j),
+                digit,
+                technique: Technique::NakedPair,
+            })
+            .collect_vec();
+        assert_eq!(log, expected);
+    }
+
+    #[test]
+    fn conjugate_pairs_works() {
+        let text = " 1
+69  2  57
+    692
+  9   4
+47     2
+581 9   3
+  5  86
+ 4 2  8 1
+   6   4";
+        let mut board = Sudoku::from_text(text).unwrap();
+        board.propagate_with(Technique::NakedTriple);
+        let pairs = board.conjugate_pairs(8);
+        println!("{pairs:?}");
+        assert!(pairs.contains(&(UnitKind::Column, 4, (3, 4), (4, 4))));
+        assert!(pairs.contains(&(UnitKind::Column, 8, (3, 8), (4, 8))));
+        for (kind, index, a, b) in pairs {
+            assert!(kind.cells(index).contains(&a) && kind.cells(index).contains(&b));
+            assert!(a < b);
+        }
+        assert!(Sudoku::from_text("").unwrap().conjugate_pairs(8).is_empty());
+    }
+
+    #[test]
+    fn find_x_wings_works() {
+        let text = " 1
+69  2  57
+    692
+  9   4
+47     2
+581 9   3
+  5  86
+ 4 2  8 1
+   6   4";
+        let mut board = Sudoku::from_text(text).unwrap();
+        board.propagate_with(Technique::NakedTriple);
+        let x_wings = board.find_x_wings();
+        println!("{board}\n{x_wings:?}");
+        let expected = XWing {
+            digit: 8,
+            rows: [3, 4],
+            cols: [4, 8],
+        };
+        assert_eq!(x_wings, vec![expected]);
+        // The columns hold the pattern, so 8 goes out of the rest of rows 3 and 4
+        for j in expected.cols {
+            let places = (0..N)
+                .filter(|&i| {
+                    board.get(i, j).get_number().is_none() && board.get_guesses(i, j).contains(&8)
+                })
+                .collect_vec();
+            assert_eq!(places, expected.rows);
+        }
+        assert!(board.get_guesses(3, 7).contains(&8));
+        let solution = board.solved().unwrap();
+        let corners = expected
+            .rows
+            .iter()
+            .cartesian_product(expected.cols)
+            .filter(|&(&i, j)| *solution.get(i, j) == Item::Number(8))
+            .count();
+        assert_eq!(corners, 2);
+        assert!(Sudoku::from_text("").unwrap().find_x_wings().is_empty());
+    }
+
+    #[test]
+    fn x_wing_elimination_works() {
+        let text = " 1
+69  2  57
+    692
+  9   4
+47     2
+581 9   3
+  5  86
+ 4 2  8 1
+   6   4";
+        let mut board = Sudoku::from_text(text).unwrap();
+        let mut stalled = board.clone();
+        stalled.propagate_with(Technique::NakedTriple);
+        assert!(!stalled.is_solved());
+        let log = board.propagate();
+        let expected = Elimination {
+            cell: (3, 7),
+            digit: 8,
+            technique: Technique::XWing,
+        };
+        assert_eq!(log, vec![expected]);
+        assert!(board.is_solved());
+    }
+
+    #[test]
+    fn swordfish_works() {
+        let text =
+            "529410703006003002003200000052300076637050200190627530300069420200830600960742305";
+        let mut board = Sudoku::from_sudokuwiki(text).unwrap();
+        let mut stalled = board.clone();
+        let log = stalled.propagate_with(Technique::XWing);
+        assert!(!stalled.is_solved());
+        assert!(log.iter().all(|e| e.digit != 8));
+        let log = board.propagate();
+        let swordfish = log
+            .into_iter()
+            .filter(|e| e.technique == Technique::Swordfish)
+            .map(|e| (e.cell, e.digit))
+            .collect::<Vec<_>>();
+        let expected = [(1, 1), (2, 1), (2, 5), (3, 5), (1, 7), (2, 7), (2, 8)];
+        assert_eq!(swordfish, expected.map(|cell| (cell, 8)));
+        assert!(board.is_solved());
+    }
+}
@@ -0,0 +1,69 @@
+//! Allocations saved by the board pool of the sequential searches. Counting them takes a
+//! global allocator, so they are measured in their own test binary.
+#![cfg(feature = "std")]
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+use sudoku_solver::{solutions, solve_many, solve_sudoku, solve_sudoku_recursive, Sudoku};
+
+/// Counts the allocations of each thread, so the harness threads don't add up
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Result of `f` and the number of allocations it made
+fn count_allocations<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATIONS.with(Cell::get);
+    let result = f();
+    (result, ALLOCATIONS.with(Cell::get) - before)
+}
+
+const HARD: &str = "1    7 9
+ 3  2   8
+  96  5
+  53  9
+ 1  8   2
+6    4
+3      1
+ 4      7
+  7   3";
+
+#[test]
+fn pooled_boards_save_allocations() {
+    let board = Sudoku::from_text(HARD).unwrap();
+    // Both search the same tree in the same order, the recursive search clones every branch
+    let (pooled, pooled_allocations) =
+        count_allocations(|| solutions(board.clone()).next().unwrap());
+    let (unpooled, unpooled_allocations) =
+        count_allocations(|| solve_sudoku_recursive(board.clone(), 81).unwrap().0);
+    println!("Allocations: {pooled_allocations} (pooled) {unpooled_allocations} (unpooled)");
+    assert_eq!(pooled, unpooled);
+    assert!(pooled_allocations * 2 < unpooled_allocations);
+
+    // The pool outlives each board
+    let boards = vec![board; 3];
+    let (results, many_allocations) = count_allocations(|| solve_many(boards.clone()));
+    let (expected, single_allocations) =
+        count_allocations(|| boards.into_iter().map(solve_sudoku).collect::<Vec<_>>());
+    println!("Allocations: {many_allocations} (solve_many) {single_allocations} (solve_sudoku)");
+    assert_eq!(results, expected);
+    assert!(many_allocations < single_allocations);
+}
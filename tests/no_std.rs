@@ -0,0 +1,30 @@
+//! Solving from a `no_std` crate, the library only needs `alloc` for it. Checked with
+//! `cargo test --no-default-features --features alloc`.
+#![cfg(feature = "alloc")]
+#![no_std]
+
+extern crate alloc;
+
+use alloc::format;
+use sudoku_solver::{count_solutions, solve_sudoku, solve_with_trace, Sudoku};
+
+const PUZZLE: &str =
+    "100007090030020008009600500005300900010080002600004000300000010040000007007000300";
+const SOLUTION: &str =
+    "162857493534129678789643521475312986913586742628794135356478219241935867897261354";
+
+#[test]
+fn solves_without_std() {
+    let board = Sudoku::from_sudokuwiki(PUZZLE).unwrap();
+    let expected = Sudoku::from_sudokuwiki(SOLUTION).unwrap();
+    assert_eq!(count_solutions(board.clone(), 2), 1);
+
+    let (solution, _) = solve_sudoku(board.clone()).unwrap();
+    assert_eq!(solution, expected);
+    assert!(format!("{solution}").contains("|1 6 2|8 5 7|4 9 3|"));
+
+    // Unlike the board search, the traced search keeps a visited set
+    let (traced, trace) = solve_with_trace(board.clone()).unwrap();
+    assert_eq!(traced, expected);
+    assert_eq!(trace.len(), 81 - board.clue_count());
+}
@@ -0,0 +1,35 @@
+#![cfg(feature = "std")]
+
+use sudoku_solver::{solve_sudoku, Sudoku};
+
+/// Puzzles with unique solutions, one `puzzle,solution` pair of 81 digit strings per line
+const BANK: &str = include_str!("data/puzzles.txt");
+
+/// Parses the bank, skipping blank and `#` comment lines
+fn load_bank() -> Vec<(Sudoku, Sudoku)> {
+    BANK.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|(k, line)| {
+            let (puzzle, solution) = line
+                .split_once(',')
+                .unwrap_or_else(|| panic!("Line {k}: expected a puzzle,solution pair"));
+            let parse = |s: &str| {
+                Sudoku::from_sudokuwiki(s).unwrap_or_else(|err| panic!("Line {k}: {err}"))
+            };
+            (parse(puzzle), parse(solution))
+        })
+        .collect()
+}
+
+#[test]
+fn solves_puzzle_bank() {
+    let bank = load_bank();
+    assert!(bank.len() >= 50);
+    for (k, (puzzle, expected)) in bank.into_iter().enumerate() {
+        assert!(puzzle.is_givens_subset_of(&expected), "Puzzle {k}");
+        let (solution, _) =
+            solve_sudoku(puzzle).unwrap_or_else(|(err, _)| panic!("Puzzle {k}: {err}"));
+        assert!(solution.solution_eq(&expected), "Puzzle {k}:\n{solution}");
+    }
+}